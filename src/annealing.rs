@@ -0,0 +1,253 @@
+use crate::{AnnealingConfig, U256};
+
+use super::chain::create_chain_mask;
+use super::connectivity;
+use super::local_search::LocalSearch;
+use super::words::pretty_format_index_chain;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use std::time::Instant;
+
+/// How many iterations pass between two checks of the clock
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// How many words a single truncation removes at most
+const MAX_TRUNCATED_WORDS: usize = 3;
+
+pub fn find_longest(
+    connectivity_index_table: &[Vec<u8>],
+    words: &[String],
+    config: &AnnealingConfig,
+) -> Vec<u8> {
+    let mut rng = match config.seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_entropy(),
+    };
+
+    let mut state = State::new(connectivity_index_table, &mut rng);
+    let local_search = LocalSearch::new(connectivity_index_table);
+
+    let mut longest = Vec::new();
+    let mut temperature = config.initial_temperature;
+    let started = Instant::now();
+
+    for iteration in 0u64.. {
+        if iteration.is_multiple_of(CLOCK_INTERVAL) {
+            let progress = started.elapsed().as_secs_f64() / config.time_limit.as_secs_f64();
+
+            if progress >= 1f64 {
+                break;
+            }
+
+            // Exponential cooling from the initial to the final temperature over the time limit
+            temperature = config.initial_temperature
+                * (config.final_temperature / config.initial_temperature).powf(progress);
+        }
+
+        state.apply_random_move(temperature, &mut rng);
+
+        if state.chain.len() > longest.len() {
+            // Jump straight to the best chain in the neighbourhood
+            local_search.improve_chain(&mut state.chain);
+            state.chain_mask = create_chain_mask(&state.chain);
+
+            longest = state.chain.clone();
+
+            println!(
+                "Longest chain ({}): {}",
+                longest.len(),
+                pretty_format_index_chain(words, &longest)
+            );
+        }
+    }
+
+    longest
+}
+
+/// The current chain of the annealing process and the lookup tables needed to modify it
+struct State<'a> {
+    chain: Vec<u8>,
+    chain_mask: U256,
+    follower_table: &'a [Vec<u8>],
+    predecessor_table: Vec<Vec<u8>>,
+    follower_masks: Vec<U256>,
+    predecessor_masks: Vec<U256>,
+}
+
+impl<'a> State<'a> {
+    fn new<R: Rng>(follower_table: &'a [Vec<u8>], rng: &mut R) -> State<'a> {
+        let predecessor_table = connectivity::create_predecessor_table(follower_table);
+        let start = rng.gen_range(0..follower_table.len()) as u8;
+
+        State {
+            chain: vec![start],
+            chain_mask: U256::one() << start,
+            follower_masks: connectivity::create_index_masks(follower_table),
+            predecessor_masks: connectivity::create_index_masks(&predecessor_table),
+            follower_table,
+            predecessor_table,
+        }
+    }
+
+    fn apply_random_move<R: Rng>(&mut self, temperature: f64, rng: &mut R) {
+        match rng.gen_range(0..5) {
+            0 => self.extend(rng),
+            1 => self.truncate(temperature, rng),
+            2 => self.reroute(temperature, rng),
+            3 => self.splice(temperature, rng),
+            _ => self.rotate(rng),
+        }
+    }
+
+    /// Appends an unused follower or prepends an unused predecessor
+    fn extend<R: Rng>(&mut self, rng: &mut R) {
+        let chain_mask = self.chain_mask;
+
+        if rng.gen() {
+            let last = *self.chain.last().unwrap() as usize;
+
+            if let Some(word) = pick_random(
+                self.follower_table[last]
+                    .iter()
+                    .cloned()
+                    .filter(|&w| !chain_mask.bit(w as usize)),
+                rng,
+            ) {
+                self.chain.push(word);
+                self.chain_mask = chain_mask | U256::one() << word;
+            }
+        } else {
+            let first = self.chain[0] as usize;
+
+            if let Some(word) = pick_random(
+                self.predecessor_table[first]
+                    .iter()
+                    .cloned()
+                    .filter(|&w| !chain_mask.bit(w as usize)),
+                rng,
+            ) {
+                self.chain.insert(0, word);
+                self.chain_mask = chain_mask | U256::one() << word;
+            }
+        }
+    }
+
+    /// Removes a few words from either end
+    fn truncate<R: Rng>(&mut self, temperature: f64, rng: &mut R) {
+        if self.chain.len() < 2 {
+            return;
+        }
+
+        let count = rng.gen_range(1..=(self.chain.len() - 1).min(MAX_TRUNCATED_WORDS));
+
+        if !accept(-(count as f64), temperature, rng) {
+            return;
+        }
+
+        if rng.gen() {
+            self.chain.truncate(self.chain.len() - count);
+        } else {
+            self.chain.drain(..count);
+        }
+
+        self.chain_mask = create_chain_mask(&self.chain);
+    }
+
+    /// Cuts the chain after a random word and continues with a different follower instead
+    fn reroute<R: Rng>(&mut self, temperature: f64, rng: &mut R) {
+        if self.chain.len() < 2 {
+            return;
+        }
+
+        let position = rng.gen_range(0..self.chain.len() - 1);
+        let kept_mask = create_chain_mask(&self.chain[..=position]);
+        let replaced = self.chain[position + 1];
+
+        let word = match pick_random(
+            self.follower_table[self.chain[position] as usize]
+                .iter()
+                .cloned()
+                .filter(|&w| w != replaced && !kept_mask.bit(w as usize)),
+            rng,
+        ) {
+            Some(word) => word,
+            None => return,
+        };
+
+        let delta = (position + 2) as f64 - self.chain.len() as f64;
+
+        if accept(delta, temperature, rng) {
+            self.chain.truncate(position + 1);
+            self.chain.push(word);
+            self.chain_mask = kept_mask | U256::one() << word;
+        }
+    }
+
+    /// Inserts an unused word between two neighbours that both link to it, or cuts out a word
+    /// whose neighbours link to each other directly
+    fn splice<R: Rng>(&mut self, temperature: f64, rng: &mut R) {
+        if self.chain.len() < 2 {
+            return;
+        }
+
+        if rng.gen() {
+            let position = rng.gen_range(0..self.chain.len() - 1);
+            let chain_mask = self.chain_mask;
+            let next_predecessors = self.predecessor_masks[self.chain[position + 1] as usize];
+
+            if let Some(word) = pick_random(
+                self.follower_table[self.chain[position] as usize]
+                    .iter()
+                    .cloned()
+                    .filter(|&w| next_predecessors.bit(w as usize) && !chain_mask.bit(w as usize)),
+                rng,
+            ) {
+                self.chain.insert(position + 1, word);
+                self.chain_mask = chain_mask | U256::one() << word;
+            }
+        } else if self.chain.len() > 2 {
+            let position = rng.gen_range(1..self.chain.len() - 1);
+
+            let bridged = self.follower_masks[self.chain[position - 1] as usize]
+                .bit(self.chain[position + 1] as usize);
+
+            if bridged && accept(-1f64, temperature, rng) {
+                let word = self.chain.remove(position);
+                self.chain_mask = self.chain_mask & !(U256::one() << word);
+            }
+        }
+    }
+
+    /// If the last word links back to the first, the chain is really a cycle and can start at
+    /// any of its words. This frees up different ends for extension.
+    fn rotate<R: Rng>(&mut self, rng: &mut R) {
+        let first = self.chain[0] as usize;
+        let last = *self.chain.last().unwrap() as usize;
+
+        if self.chain.len() > 1 && self.follower_masks[last].bit(first) {
+            let position = rng.gen_range(1..self.chain.len());
+
+            self.chain.rotate_left(position);
+        }
+    }
+}
+
+/// Metropolis criterion: improvements are always accepted, deteriorations with a probability
+/// that shrinks with the temperature
+fn accept<R: Rng>(delta: f64, temperature: f64, rng: &mut R) -> bool {
+    delta >= 0f64 || rng.gen::<f64>() < (delta / temperature).exp()
+}
+
+fn pick_random<I, R>(mut candidates: I, rng: &mut R) -> Option<u8>
+where
+    I: Iterator<Item = u8> + Clone,
+    R: Rng,
+{
+    let count = candidates.clone().count();
+
+    if count == 0 {
+        None
+    } else {
+        candidates.nth(rng.gen_range(0..count))
+    }
+}
@@ -0,0 +1,97 @@
+use super::chain::create_chain_mask;
+use super::words::pretty_format_index_chain;
+use crate::U256;
+use rayon::prelude::*;
+use std::cmp::Reverse;
+
+/// A partial chain in the beam, along with the lookahead used to rank it
+struct Candidate {
+    chain: Vec<u8>,
+    chain_mask: U256,
+    lookahead: (usize, usize),
+}
+
+pub fn find_longest(
+    connectivity_index_table: &[Vec<u8>],
+    words: &[String],
+    width: usize,
+) -> Vec<u8> {
+    debug_assert!(width > 0);
+
+    let mut beam = (0..connectivity_index_table.len() as u8)
+        .map(|start| create_candidate(vec![start], connectivity_index_table))
+        .collect::<Vec<_>>();
+
+    select_best(&mut beam, width);
+
+    let mut longest = beam[0].chain.clone();
+
+    loop {
+        // Chains in the beam all have the same length, so only their lookahead matters
+        let mut next_beam = beam
+            .par_iter()
+            .flat_map(|candidate| {
+                let last = *candidate.chain.last().unwrap() as usize;
+
+                connectivity_index_table[last]
+                    .par_iter()
+                    .filter(|&&follower| !candidate.chain_mask.bit(follower as usize))
+                    .map(|&follower| {
+                        let mut chain = candidate.chain.clone();
+                        chain.push(follower);
+
+                        create_candidate(chain, connectivity_index_table)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if next_beam.is_empty() {
+            break;
+        }
+
+        select_best(&mut next_beam, width);
+
+        beam = next_beam;
+        longest = beam[0].chain.clone();
+
+        println!(
+            "Finished level {} - Longest chain until now ({}): {}",
+            longest.len() - 1,
+            longest.len(),
+            pretty_format_index_chain(words, &longest)
+        );
+    }
+
+    longest
+}
+
+fn create_candidate(chain: Vec<u8>, connectivity_index_table: &[Vec<u8>]) -> Candidate {
+    let chain_mask = create_chain_mask(&chain);
+    let last = *chain.last().unwrap() as usize;
+
+    // Rank by how many ways there are to continue the chain, and then by how many ways there are
+    // to continue from each of those
+    let lookahead = connectivity_index_table[last]
+        .iter()
+        .filter(|&&follower| !chain_mask.bit(follower as usize))
+        .fold((0, 0), |(count, degrees), &follower| {
+            (
+                count + 1,
+                degrees + connectivity_index_table[follower as usize].len(),
+            )
+        });
+
+    Candidate {
+        chain,
+        chain_mask,
+        lookahead,
+    }
+}
+
+/// Keeps the `width` candidates with the best lookahead. The sort is stable, so ties are
+/// resolved by the order in which candidates were generated and the search stays deterministic.
+fn select_best(candidates: &mut Vec<Candidate>, width: usize) {
+    candidates.sort_by_key(|candidate| Reverse(candidate.lookahead));
+    candidates.truncate(width);
+}
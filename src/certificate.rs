@@ -0,0 +1,374 @@
+//! Optimality certificates for the longest chain.
+//!
+//! A certificate lists, for every start word, an upper bound on the length of the chains that
+//! start with it, in the order in which the search published those bounds. It doesn't record
+//! why each bound holds, because the justification can be derived from the certificate itself:
+//! the only pruning the check allows is to skip a follower that was published earlier and whose
+//! bound shows that continuing with it can't exceed the bound being checked. Every bound
+//! therefore only depends on bounds before it, so checking them all is a proof by induction
+//! over the publishing order, and recording the pruned branches would only make the file larger.
+//!
+//! The check is much cheaper than a new search. A search only knows the longest chain found so
+//! far and has to keep looking until it has found the longest one, while the check knows the
+//! bound it has to confirm from the start, prunes against it right away and stops as soon as any
+//! chain exceeds it. Since the bounds don't depend on each other's checks, all start words are
+//! checked in parallel instead of in publishing order.
+
+use super::chain::create_chain_mask;
+use super::{connectivity, words};
+use crate::{Certificate, ChainInfo, U256};
+use rayon::prelude::*;
+use std::io::{self, Write};
+
+const HEADER: &str = "wordchain certificate";
+
+impl Certificate {
+    /// Builds a certificate from the result of a sorted search. `estimates` contains the
+    /// (start word, estimate) pairs in the order in which they were published.
+    pub(crate) fn new(
+        sorted_words: &[String],
+        min_overlap: usize,
+        longest_chain: &[u8],
+        estimates: &[(u8, u8)],
+    ) -> Certificate {
+        let mut positions = vec![0u8; sorted_words.len()];
+
+        for (position, &(start_index, _)) in estimates.iter().enumerate() {
+            positions[start_index as usize] = position as u8;
+        }
+
+        Certificate {
+            min_overlap,
+            words: estimates
+                .iter()
+                .map(|&(start_index, _)| sorted_words[start_index as usize].clone())
+                .collect(),
+            bounds: estimates.iter().map(|&(_, estimate)| estimate).collect(),
+            chain: longest_chain
+                .iter()
+                .map(|&index| positions[index as usize])
+                .collect(),
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "min-overlap {}", self.min_overlap)?;
+
+        let chain = self
+            .chain
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        writeln!(writer, "chain {}", chain.join(" "))?;
+
+        for (word, bound) in self.words.iter().zip(&self.bounds) {
+            writeln!(writer, "bound {} {}", bound, word)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn parse(content: &str) -> Result<Certificate, &'static str> {
+        const MALFORMED: &str = "The certificate is malformed.";
+
+        let mut lines = content.lines();
+
+        if lines.next() != Some(HEADER) {
+            return Err("This is not a wordchain certificate.");
+        }
+
+        let min_overlap = lines
+            .next()
+            .and_then(|line| line.strip_prefix("min-overlap "))
+            .and_then(|value| value.parse().ok())
+            .ok_or(MALFORMED)?;
+
+        let chain = lines
+            .next()
+            .and_then(|line| line.strip_prefix("chain"))
+            .ok_or(MALFORMED)?
+            .split_whitespace()
+            .map(|index| index.parse().map_err(|_| MALFORMED))
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        let mut words = Vec::new();
+        let mut bounds = Vec::new();
+
+        for line in lines.filter(|line| !line.is_empty()) {
+            let mut parts = line.splitn(3, ' ');
+
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("bound"), Some(bound), Some(word)) if !word.is_empty() => {
+                    bounds.push(bound.parse().map_err(|_| MALFORMED)?);
+                    words.push(word.to_owned());
+                }
+                _ => return Err(MALFORMED),
+            }
+        }
+
+        Ok(Certificate {
+            min_overlap,
+            words,
+            bounds,
+            chain,
+        })
+    }
+
+    /// Checks that the certificate was created for the given words and minimum overlap. The
+    /// order of the words doesn't matter.
+    pub fn check_words(&self, words: &[String], min_overlap: usize) -> Result<(), &'static str> {
+        if self.min_overlap != min_overlap {
+            return Err("The certificate was created with a different minimum overlap.");
+        }
+
+        let mut certified = self.words.iter().collect::<Vec<_>>();
+        let mut given = words.iter().collect::<Vec<_>>();
+
+        certified.sort_unstable();
+        given.sort_unstable();
+        given.dedup();
+
+        if certified != given {
+            return Err("The certificate was created for a different word list.");
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no chain is longer than the one in the certificate, and returns that chain.
+    ///
+    /// Each bound is checked by a search that may only rely on the bounds listed before it.
+    /// Since the search knows the bound it has to confirm, it can prune far more aggressively
+    /// than the search that found it, and all bounds can be checked in parallel.
+    pub fn verify(&self) -> Result<ChainInfo, &'static str> {
+        if self.words.is_empty() || self.words.len() > 256 || self.words.len() != self.bounds.len()
+        {
+            return Err("The certificate must contain between 1 and 256 words, each with a bound.");
+        }
+
+        let connectivity_map =
+            connectivity::create_connectivity_map(&self.words, self.min_overlap);
+
+        if connectivity_map.len() != self.words.len() {
+            return Err("The certificate contains duplicate words.");
+        }
+
+        let connectivity_index_table =
+            connectivity::create_connectivity_index_table(&self.words, &connectivity_map);
+
+        // The chain has to be real ...
+        if self.chain.is_empty()
+            || self
+                .chain
+                .iter()
+                .any(|&index| index as usize >= self.words.len())
+        {
+            return Err("The chain in the certificate refers to unknown words.");
+        }
+
+        let mut used = vec![false; self.words.len()];
+
+        if self
+            .chain
+            .iter()
+            .any(|&index| std::mem::replace(&mut used[index as usize], true))
+        {
+            return Err("The chain in the certificate uses a word more than once.");
+        }
+
+        let linked = self.chain.windows(2).all(|pair| {
+            connectivity_index_table[pair[0] as usize].contains(&pair[1])
+        });
+
+        if !linked {
+            return Err("The chain in the certificate contains words that don't overlap.");
+        }
+
+        // ... as long as the longest bound ...
+        if self.bounds.iter().any(|&bound| bound as usize > self.chain.len()) {
+            return Err("The certificate has a bound that is longer than its chain.");
+        }
+
+        // ... and each bound has to hold
+        let all_bounds_hold = (0..self.words.len())
+            .into_par_iter()
+            .all(|start| self.bound_holds(start as u8, &connectivity_index_table));
+
+        if !all_bounds_hold {
+            return Err("The certificate has a bound that doesn't hold.");
+        }
+
+        Ok(ChainInfo {
+            len: self.chain.len() as u8,
+            chain: words::pretty_format_index_chain(&self.words, &self.chain),
+            score: self.chain.len() as u64,
+            certificate: None,
+        })
+    }
+
+    /// Searches for a chain starting with `start` that is longer than its bound. Followers with
+    /// an earlier bound are skipped if that bound shows they can't lead to such a chain.
+    fn bound_holds(&self, start: u8, follower_table: &[Vec<u8>]) -> bool {
+        let bound = self.bounds[start as usize] as usize;
+
+        let mut chain = vec![start];
+        let mut chain_mask = create_chain_mask(&chain);
+        let mut follower_indices = vec![0usize];
+
+        while let Some(follower_index) = follower_indices.last_mut() {
+            if chain.len() > bound {
+                return false;
+            }
+
+            let followers = &follower_table[*chain.last().unwrap() as usize];
+
+            match followers.get(*follower_index) {
+                Some(&follower) => {
+                    *follower_index += 1;
+
+                    let pruned = follower < start
+                        && chain.len() + self.bounds[follower as usize] as usize <= bound;
+
+                    if !pruned && !chain_mask.bit(follower as usize) {
+                        chain.push(follower);
+                        chain_mask = chain_mask | U256::one() << follower;
+                        follower_indices.push(0);
+                    }
+                }
+                None => {
+                    let word = chain.pop().unwrap();
+                    chain_mask = chain_mask & !(U256::one() << word);
+                    follower_indices.pop();
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Certificate, Config, Mode, Objective, SortedSearchConfig};
+
+    const WORDS: &[&str] = &[
+        "Jacob",
+        "Michael",
+        "Joshua",
+        "Matthew",
+        "Christopher",
+        "Andrew",
+        "Daniel",
+        "Ethan",
+        "Joseph",
+        "William",
+        "Anthony",
+        "Nicholas",
+        "David",
+        "Alexander",
+        "Ryan",
+        "Tyler",
+        "James",
+        "John",
+        "Jonathan",
+        "Brandon",
+    ];
+
+    fn words() -> Vec<String> {
+        WORDS.iter().map(|&word| word.to_owned()).collect()
+    }
+
+    /// Runs a normal search and returns the length of its chain along with its certificate
+    fn certified_search(pipeline: Option<usize>) -> (u8, Certificate) {
+        let search_config = SortedSearchConfig {
+            granularity: None,
+            pipeline,
+            verbose: false,
+            certificate: true,
+        };
+
+        let config = Config {
+            min_overlap: 1,
+            metadata: None,
+            pattern: None,
+            objective: Objective::Length,
+            max_chars: None,
+            word_count: None,
+            mode: Mode::Normal(&search_config),
+        };
+
+        let chain_info = crate::find_longest_chain(words(), &config).unwrap();
+        (chain_info.len, chain_info.certificate.unwrap())
+    }
+
+    fn write(certificate: &Certificate) -> String {
+        let mut text = Vec::new();
+        certificate.write(&mut text).unwrap();
+        String::from_utf8(text).unwrap()
+    }
+
+    #[test]
+    fn written_certificates_verify() {
+        for &pipeline in &[None, Some(4)] {
+            let (len, certificate) = certified_search(pipeline);
+            let parsed = Certificate::parse(&write(&certificate)).unwrap();
+
+            assert_eq!(parsed.min_overlap, certificate.min_overlap);
+            assert_eq!(parsed.words, certificate.words);
+            assert_eq!(parsed.bounds, certificate.bounds);
+            assert_eq!(parsed.chain, certificate.chain);
+
+            assert_eq!(parsed.verify().map(|chain_info| chain_info.len), Ok(len));
+        }
+    }
+
+    #[test]
+    fn tampered_bounds_are_rejected() {
+        let (_, certificate) = certified_search(None);
+
+        // Every bound is the exact length of the longest chain from its word, so none can be lower
+        for i in 0..certificate.bounds.len() {
+            let mut tampered = Certificate::parse(&write(&certificate)).unwrap();
+            tampered.bounds[i] -= 1;
+
+            assert!(tampered.verify().is_err(), "bound {} was lowered", i);
+        }
+
+        let mut tampered = Certificate::parse(&write(&certificate)).unwrap();
+        tampered.chain.pop();
+        assert!(tampered.verify().is_err());
+
+        let mut tampered = Certificate::parse(&write(&certificate)).unwrap();
+        tampered.chain.swap(0, 1);
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn malformed_certificates_are_rejected() {
+        let text = write(&certified_search(None).1);
+
+        assert!(Certificate::parse(&text.replacen("wordchain", "wordchains", 1)).is_err());
+        assert!(Certificate::parse(&text.replacen("min-overlap 1", "min-overlap x", 1)).is_err());
+        assert!(Certificate::parse(&text.replacen("bound ", "bound", 1)).is_err());
+        assert!(Certificate::parse(&text.replacen("chain ", "chains ", 1)).is_err());
+    }
+
+    #[test]
+    fn words_have_to_match() {
+        let (_, certificate) = certified_search(None);
+
+        let mut shuffled = words();
+        shuffled.reverse();
+        shuffled.push("Jacob".to_owned());
+
+        assert_eq!(certificate.check_words(&shuffled, 1), Ok(()));
+        assert!(certificate.check_words(&shuffled, 2).is_err());
+
+        shuffled.push("Zoe".to_owned());
+        assert!(certificate.check_words(&shuffled, 1).is_err());
+
+        assert!(certificate.check_words(&words()[1..], 1).is_err());
+    }
+}
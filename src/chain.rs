@@ -1,148 +1,902 @@
+use super::pattern::TagAutomaton;
 use super::{tasks, words};
-use crate::U256;
+use crate::{SortedSearchConfig, WordMetadata, U256};
 use rayon::prelude::*;
 use std::cmp;
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
 
+/// Upper bounds on the score of the best chain starting with each word. A value of 0 means that
+/// no estimate has been published for that word yet. A missing or late estimate only weakens
+/// pruning, it never changes the result. Estimates are published with release ordering, so that
+/// the publishing order recorded alongside them is consistent with the estimates each search saw.
+type LongestEstimates = [AtomicU64];
+
+/// What is already known before a search starts, usually from an earlier search on almost the
+/// same words
+#[derive(Default)]
+pub struct KnownBounds {
+    /// An upper bound on the score of the best chain starting with each word, where 0 means that
+    /// nothing is known. May be shorter than the word list.
+    pub estimates: Vec<u64>,
+
+    /// The score of a chain that is known to exist. Start words that can't beat it according to
+    /// their estimate aren't searched, so the search may come back with a worse chain, or none at
+    /// all.
+    pub lower_bound: u64,
+}
+
+/// A chain along with its score, which is the sum of the weights of its words
+#[derive(Clone, Default)]
+struct ScoredChain {
+    words: Vec<u8>,
+    score: u64,
+}
+
+impl ScoredChain {
+    /// A higher score is better, and more words break ties
+    fn is_better_than(&self, other: &ScoredChain) -> bool {
+        (self.score, self.words.len()) > (other.score, other.words.len())
+    }
+}
+
+/// How many characters each word adds to the glued chain. Only needed if the number of
+/// characters is bounded.
+struct RenderedChars {
+    /// The characters of each word on its own, which is what it adds at the start of a chain
+    words: Vec<usize>,
+
+    /// What each follower in the connectivity index table adds after the word before it, which
+    /// is its length minus the overlap
+    followers: Vec<Vec<usize>>,
+}
+
+impl RenderedChars {
+    fn new(connectivity_index_table: &[Vec<u8>], sorted_words: &[String]) -> RenderedChars {
+        let followers = connectivity_index_table
+            .iter()
+            .zip(sorted_words)
+            .map(|(followers, left)| {
+                followers
+                    .iter()
+                    .map(|&follower| {
+                        let right = &sorted_words[follower as usize];
+                        let overlap = words::overlapping_chars(left, right);
+
+                        // Gluing drops the overlap from the left word, see pretty_format_index_chain
+                        right.chars().count() - left[left.len() - overlap..].chars().count()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        RenderedChars {
+            words: sorted_words.iter().map(|word| word.chars().count()).collect(),
+            followers,
+        }
+    }
+}
+
+/// Restricts which chains count as a result. Chains that don't count are searched anyway, since
+/// they may be the beginning of one that does, unless they can't match the tag pattern anymore.
+pub struct ChainConstraints {
+    /// Words that may start a chain
+    pub start_mask: U256,
+
+    /// Words that may end a chain
+    pub end_mask: U256,
+
+    /// Words that every chain has to contain
+    pub required_mask: U256,
+
+    /// Decides whether the tags of a chain match a pattern. Its state is tracked next to the
+    /// chain, and chains are abandoned as soon as they can't match anymore.
+    pub automaton: Option<TagAutomaton>,
+
+    /// The most characters the glued chain may have. Chains are abandoned as soon as they get
+    /// longer.
+    pub max_chars: Option<usize>,
+
+    /// The exact number of words of a chain. Chains are never extended beyond it.
+    pub word_count: Option<usize>,
+}
+
+impl ChainConstraints {
+    pub fn none(word_count: usize) -> ChainConstraints {
+        let all = create_chain_mask(&(0..word_count).map(|w| w as u8).collect::<Vec<_>>());
+
+        ChainConstraints {
+            start_mask: all,
+            end_mask: all,
+            required_mask: U256::zero(),
+            automaton: None,
+            max_chars: None,
+            word_count: None,
+        }
+    }
+
+    /// Builds the constraints from the metadata of each sorted word
+    pub fn new(metadata: &[&WordMetadata], automaton: Option<TagAutomaton>) -> ChainConstraints {
+        let unrestricted = ChainConstraints::none(metadata.len());
+
+        let marked = |is_marked: fn(&WordMetadata) -> bool| {
+            metadata
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| is_marked(m))
+                .fold(U256::zero(), |acc, (w, _)| acc | U256::one() << w)
+        };
+
+        let start_mask = marked(|m| m.start);
+        let end_mask = marked(|m| m.end);
+
+        ChainConstraints {
+            start_mask: if start_mask.is_zero() {
+                unrestricted.start_mask
+            } else {
+                start_mask
+            },
+            end_mask: if end_mask.is_zero() {
+                unrestricted.end_mask
+            } else {
+                end_mask
+            },
+            required_mask: marked(|m| m.required),
+            automaton,
+            max_chars: None,
+            word_count: None,
+        }
+    }
+
+    fn start_state(&self) -> u16 {
+        self.automaton.as_ref().map_or(0, TagAutomaton::start)
+    }
+
+    /// The automaton state after `word` was added to a chain in `state`, or `None` if the chain
+    /// can't match the pattern anymore
+    fn next_state(&self, state: u16, word: u8) -> Option<u16> {
+        match &self.automaton {
+            Some(automaton) => {
+                Some(automaton.next(state, word)).filter(|&s| s != TagAutomaton::DEAD)
+            }
+            None => Some(state),
+        }
+    }
+
+    fn can_start_with(&self, word: u8) -> bool {
+        self.start_mask.bit(word as usize) && self.next_state(self.start_state(), word).is_some()
+    }
+
+    /// Whether a chain with `len` words and `chars` characters is within the length bounds
+    fn fits(&self, len: usize, chars: usize) -> bool {
+        self.word_count.is_none_or(|word_count| len <= word_count)
+            && self.max_chars.is_none_or(|max_chars| chars <= max_chars)
+    }
+
+    fn accepts(&self, chain_len: usize, chain_mask: U256, last: u8, state: u16) -> bool {
+        self.word_count.is_none_or(|word_count| chain_len == word_count)
+            && self.end_mask.bit(last as usize)
+            && chain_mask & self.required_mask == self.required_mask
+            && self
+                .automaton
+                .as_ref()
+                .is_none_or(|automaton| automaton.is_accepting(state))
+    }
+}
+
+/// Returns the chain with the highest score, where each word scores its weight, along with the
+/// (start word, estimate) pairs in the order in which they were published. Each estimate only
+/// relies on the estimates published before it. Start words that the constraints rule out are
+/// skipped and don't publish an estimate. With a weight of 1 for every word, this is simply the
+/// longest chain.
 pub fn find_longest_chain_parallel(
-    connectivity_index_table: &Vec<Vec<u8>>,
-    sorted_words: &Vec<String>,
+    connectivity_index_table: &[Vec<u8>],
+    sorted_words: &[String],
+    weights: &[u64],
+    sorted_search_config: &SortedSearchConfig,
+    constraints: &ChainConstraints,
+    known: &KnownBounds,
+) -> (Vec<u8>, Vec<(u8, u64)>) {
+    let longest_estimates = (0..connectivity_index_table.len())
+        .map(|w| AtomicU64::new(known.estimates.get(w).cloned().unwrap_or(0)))
+        .collect::<Vec<_>>();
+
+    let publishing_order = (0..connectivity_index_table.len())
+        .map(|_| AtomicU8::new(0))
+        .collect::<Vec<_>>();
+
+    let published_count = AtomicUsize::new(0);
+
+    let rendered_chars = constraints
+        .max_chars
+        .map(|_| RenderedChars::new(connectivity_index_table, sorted_words));
+
+    let search = Search {
+        connectivity_index_table,
+        sorted_words,
+        weights,
+        rendered_chars,
+        longest_estimates: &longest_estimates,
+        lower_bound: known.lower_bound,
+        publishing_order: &publishing_order,
+        published_count: &published_count,
+        granularity: sorted_search_config.granularity,
+        verbose: sorted_search_config.verbose,
+        constraints,
+    };
+
+    let longest = match sorted_search_config.pipeline {
+        Some(depth) if depth > 1 => find_longest_chain_pipelined(&search, depth),
+        _ => find_longest_chain_sequential(&search),
+    };
+
+    let estimates = publishing_order
+        .iter()
+        .take(published_count.load(Ordering::Relaxed))
+        .map(|start_index| {
+            let start_index = start_index.load(Ordering::Relaxed);
+
+            (
+                start_index,
+                longest_estimates[start_index as usize].load(Ordering::Relaxed),
+            )
+        })
+        .collect();
+
+    (longest.words, estimates)
+}
+
+/// Everything the searches for the individual start words share
+struct Search<'a> {
+    connectivity_index_table: &'a [Vec<u8>],
+    sorted_words: &'a [String],
+
+    /// The weight of each word, which is added to the score of every chain that contains it
+    weights: &'a [u64],
+
+    /// Only present if the constraints bound the number of characters
+    rendered_chars: Option<RenderedChars>,
+
+    longest_estimates: &'a LongestEstimates,
+
+    /// Start words that can't beat this score aren't searched
+    lower_bound: u64,
+
+    publishing_order: &'a [AtomicU8],
+    published_count: &'a AtomicUsize,
     granularity: Option<u8>,
     verbose: bool,
-) -> Vec<u8> {
-    let mut global_longest = Vec::new(); // MIN OPT: Guess length
+    constraints: &'a ChainConstraints,
+}
+
+fn find_longest_chain_sequential(search: &Search) -> ScoredChain {
+    let word_count = search.connectivity_index_table.len();
+
+    let mut global_longest = ScoredChain::default(); // MIN OPT: Guess length
+
+    for start_index in 0..word_count as u8 {
+        if !search.can_start_with(start_index) {
+            continue;
+        }
+
+        let (local_longest, estimate) = search.search_start(start_index);
+
+        search.publish(start_index, estimate);
+
+        if local_longest.is_better_than(&global_longest) {
+            global_longest = local_longest;
+        }
+
+        println!(
+            "Finished word {}/{} - Longest chain until now ({}): {}",
+            start_index as u16 + 1,
+            word_count,
+            global_longest.words.len(),
+            words::pretty_format_index_chain(search.sorted_words, &global_longest.words)
+        );
+    }
+
+    global_longest
+}
+
+/// Keeps up to `depth` start words in flight at once. Start words are still handed out in sorted
+/// order, so most of them can profit from the estimates of the words before them.
+fn find_longest_chain_pipelined(search: &Search, depth: usize) -> ScoredChain {
+    let word_count = search.connectivity_index_table.len();
+    let next_start_index = AtomicUsize::new(0);
+    let longest_score_global = AtomicU64::new(0);
+
+    let (_, global_longest) = (0..depth)
+        .into_par_iter()
+        .map(|_| {
+            let mut worker_longest = (usize::MAX, ScoredChain::default());
+
+            loop {
+                let start_index = next_start_index.fetch_add(1, Ordering::Relaxed);
+
+                if start_index >= word_count {
+                    return worker_longest;
+                }
+
+                if !search.can_start_with(start_index as u8) {
+                    continue;
+                }
+
+                let (local_longest, estimate) = search.search_start(start_index as u8);
+
+                let finished = search.publish(start_index as u8, estimate);
+
+                let previous_score =
+                    longest_score_global.fetch_max(local_longest.score, Ordering::Relaxed);
+
+                if local_longest.score > previous_score {
+                    println!(
+                        "Finished word {}/{} ({}/{} done) - New longest chain ({}): {}",
+                        start_index + 1,
+                        word_count,
+                        finished,
+                        word_count,
+                        local_longest.words.len(),
+                        words::pretty_format_index_chain(search.sorted_words, &local_longest.words)
+                    );
+                } else {
+                    println!(
+                        "Finished word {}/{} ({}/{} done)",
+                        start_index + 1,
+                        word_count,
+                        finished,
+                        word_count
+                    );
+                }
+
+                // Every worker sees its start words in ascending order, so a strict comparison
+                // keeps the earliest start word on ties
+                if local_longest.is_better_than(&worker_longest.1) {
+                    worker_longest = (start_index, local_longest);
+                }
+            }
+        })
+        .reduce(
+            || (usize::MAX, ScoredChain::default()),
+            |acc, next| {
+                // Break ties by start word to match the sequential search exactly
+                if next.1.is_better_than(&acc.1) || (!acc.1.is_better_than(&next.1) && next.0 < acc.0)
+                {
+                    next
+                } else {
+                    acc
+                }
+            },
+        );
+
+    global_longest
+}
+
+impl<'a> Search<'a> {
+    fn can_start_with(&self, start_index: u8) -> bool {
+        self.constraints.can_start_with(start_index)
+            && self.constraints.fits(1, self.start_chars(start_index))
+    }
+
+    fn start_chars(&self, word: u8) -> usize {
+        self.rendered_chars
+            .as_ref()
+            .map_or(0, |rendered_chars| rendered_chars.words[word as usize])
+    }
+
+    /// The characters that the follower at `follower_index` in the connectivity index table adds
+    /// after `word`
+    fn follower_chars(&self, word: u8, follower_index: usize) -> usize {
+        self.rendered_chars.as_ref().map_or(0, |rendered_chars| {
+            rendered_chars.followers[word as usize][follower_index]
+        })
+    }
+
+    /// Makes the estimate for a start word visible to all other searches and returns how many
+    /// estimates have been published so far
+    fn publish(&self, start_index: u8, estimate: u64) -> usize {
+        // Reserve our place in the publishing order before anyone can see the estimate
+        let finished = self.published_count.fetch_add(1, Ordering::Relaxed) + 1;
+
+        self.publishing_order[finished - 1].store(start_index, Ordering::Relaxed);
+        self.longest_estimates[start_index as usize].store(estimate, Ordering::Release);
+
+        finished
+    }
+
+    /// Searches the chains beginning with `start_index`, unless a known estimate already rules out
+    /// that any of them beats the lower bound. A known estimate is kept if it is better than the
+    /// new one.
+    fn search_start(&self, start_index: u8) -> (ScoredChain, u64) {
+        let known = self.longest_estimates[start_index as usize].load(Ordering::Acquire);
+
+        if known != 0 && known <= self.lower_bound {
+            return (ScoredChain::default(), known);
+        }
 
-    let mut longest_estimates: Vec<Option<u8>> = vec![None; connectivity_index_table.len()];
+        let (local_longest, estimate) = self.find_longest_chain_for_start(start_index);
 
-    for start_index in 0..connectivity_index_table.len() as u8 {
+        let estimate = match (known, estimate) {
+            (0, _) => estimate,
+            (_, 0) => known,
+            _ => cmp::min(known, estimate),
+        };
+
+        (local_longest, estimate)
+    }
+
+    /// Searches all chains beginning with `start_index` and returns the best chain that satisfies
+    /// the constraints, along with the estimate for that start word
+    fn find_longest_chain_for_start(&self, start_index: u8) -> (ScoredChain, u64) {
         // TODO: Think about the constant value here and what to pass instead
         let chains = tasks::create_chain_tasks(
             start_index,
-            &connectivity_index_table,
-            granularity.unwrap_or(6),
+            self.connectivity_index_table,
+            self.granularity.unwrap_or(6),
         );
 
-        let (local_longest, global_estimate) = chains
+        let (local_longest, best_score) = chains
             .into_par_iter()
             .map(|c| {
-                if verbose {
+                if self.verbose {
                     let orig_chain = c.clone();
 
-                    let chain = find_partial_longest_chain(
-                        c,
-                        &longest_estimates,
-                        &connectivity_index_table,
-                    );
+                    let result = self.find_partial_longest_chain(c);
 
                     println!(
                         "Finished longest chain search for initial chain {}",
-                        words::pretty_format_index_chain(&sorted_words, &orig_chain)
+                        words::pretty_format_index_chain(self.sorted_words, &orig_chain)
                     );
 
-                    chain
+                    result
                 } else {
-                    find_partial_longest_chain(c, &longest_estimates, &connectivity_index_table)
+                    self.find_partial_longest_chain(c)
                 }
             })
             .reduce(
-                || (Vec::new(), None),
-                |(acc_longest, acc_estimate), (next_longest, next_estimate)| {
+                || (ScoredChain::default(), 0),
+                |(acc_longest, acc_score), (next_longest, next_score)| {
                     (
-                        if next_longest.len() > acc_longest.len() {
+                        if next_longest.is_better_than(&acc_longest) {
                             next_longest
                         } else {
                             acc_longest
                         },
-                        cmp::max(next_estimate, acc_estimate),
+                        cmp::max(acc_score, next_score),
                     )
                 },
             );
 
-        longest_estimates[start_index as usize] =
-            global_estimate.or(Some(local_longest.len() as u8));
+        // Branches are only pruned if they can't beat a chain that was already found, so the
+        // best chain we walked is the best chain for this start word, and its score is an exact
+        // estimate. The constraints only decide which chains count as a result, and the
+        // estimates still have to hold for all chains. Length bounds cut off branches, but the
+        // rest of a chain within the bounds is within the bounds on its own, so the estimates
+        // still hold for every chain that can be walked.
+        //
+        // A tag pattern is different: Branches that can't match it are cut off, and a word can
+        // continue differently in the middle of a chain than at its start anyway. So no estimate
+        // is published, which keeps it unknown.
+        let estimate = if self.constraints.automaton.is_some() {
+            0
+        } else {
+            best_score
+        };
 
-        if local_longest.len() > global_longest.len() {
-            global_longest = local_longest;
-        }
-
-        println!(
-            "Finished word {}/{} - Longest chain until now ({}): {}",
-            start_index as u16 + 1,
-            connectivity_index_table.len(),
-            global_longest.len(),
-            words::pretty_format_index_chain(&sorted_words, &global_longest)
-        );
+        (local_longest, estimate)
     }
 
-    global_longest
-}
+    /// Returns the best chain that starts with `chain` and satisfies the constraints, along with
+    /// the best score of all chains that were walked
+    fn find_partial_longest_chain(&self, mut chain: Vec<u8>) -> (ScoredChain, u64) {
+        let follower_table = self.connectivity_index_table;
 
-fn find_partial_longest_chain(
-    mut chain: Vec<u8>,
-    longest_estimates: &Vec<Option<u8>>,
-    follower_table: &Vec<Vec<u8>>,
-) -> (Vec<u8>, Option<u8>) {
-    let initial_len = chain.len();
+        let initial_len = chain.len();
 
-    debug_assert!(initial_len > 0);
+        debug_assert!(initial_len > 0);
 
-    // Contains our best (safe) estimate of what the longest chain for our starting chain would be
-    // Again, this actually contains the length - 1
-    let mut estimate_for_initial_chain: Option<u8> = None;
+        let mut chain_mask = create_chain_mask(&chain);
 
-    let mut chain_mask = chain
-        .iter()
-        .map(|&i| U256::one() << i)
-        .fold(U256::zero(), |acc, mask| acc + mask);
+        // The automaton state and the number of characters after each word of the chain
+        let mut states = Vec::with_capacity(chain.len());
+        let mut chars = Vec::with_capacity(chain.len());
+        let mut state = self.constraints.start_state();
+        let mut len = self.start_chars(chain[0]);
 
-    // MIN OPT: Guess the size here.
-    let mut local_longest = Vec::new();
+        for (i, &word) in chain.iter().enumerate() {
+            if i > 0 {
+                let follower_index = follower_table[chain[i - 1] as usize]
+                    .iter()
+                    .position(|&follower| follower == word)
+                    .unwrap();
+
+                len += self.follower_chars(chain[i - 1], follower_index);
+            }
 
-    let mut follower_table_indices = vec![0u8; follower_table.len()];
+            match self.constraints.next_state(state, word) {
+                Some(next_state) if self.constraints.fits(i + 1, len) => state = next_state,
+                _ => break,
+            }
 
-    loop {
-        let index = *chain.last().unwrap() as usize;
+            states.push(state);
+            chars.push(len);
+        }
 
-        let followers = &follower_table[index];
+        if states.len() < chain.len() {
+            // Chains that continue the beginning within the constraints are searched by other
+            // tasks, so the beginning itself is the best chain of this task
+            chain.truncate(states.len());
 
-        let follower_index = &mut follower_table_indices[index];
+            let score = chain.iter().map(|&w| self.weights[w as usize]).sum();
+
+            return (
+                self.find_accepted_beginning(&chain, &states, ScoredChain::default()),
+                score,
+            );
+        }
+
+        // MIN OPT: Guess the size here.
+        let mut local_longest = ScoredChain::default();
+        let mut best_score = 0;
+
+        let mut score = chain.iter().map(|&w| self.weights[w as usize]).sum::<u64>();
+
+        let mut follower_table_indices = vec![0u8; follower_table.len()];
 
         loop {
-            if let Some(follower) = followers.get(*follower_index as usize) {
-                *follower_index += 1;
+            let index = *chain.last().unwrap() as usize;
 
-                let can_be_longest = longest_estimates[*follower as usize]
-                    .and_then(|est| est.checked_add(chain.len() as u8))
-                    .map(|potential_len| {
-                        estimate_for_initial_chain = Some(cmp::max(
-                            potential_len,
-                            estimate_for_initial_chain.unwrap_or(0),
-                        ));
-                        potential_len >= local_longest.len() as u8 // we have info about a record and this can maybe be the longest chain
-                    })
-                    .unwrap_or(true);
+            let followers = &follower_table[index];
+
+            let follower_index = &mut follower_table_indices[index];
 
-                if can_be_longest && !chain_mask.bit(*follower as usize) {
-                    chain.push(*follower);
-                    chain_mask = chain_mask | U256::one() << *follower;
+            loop {
+                if let Some(follower) = followers.get(*follower_index as usize) {
+                    let follower_chars = *chars.last().unwrap()
+                        + self.follower_chars(index as u8, *follower_index as usize);
+
+                    *follower_index += 1;
+
+                    let follower_estimate =
+                        match self.longest_estimates[*follower as usize].load(Ordering::Acquire) {
+                            0 => None,
+                            est => Some(est),
+                        };
+
+                    let can_be_longest = follower_estimate
+                        .map(|est| {
+                            est + score >= local_longest.score // we have info about a record and this can maybe be the longest chain
+                        })
+                        .unwrap_or(true);
+
+                    if can_be_longest
+                        && !chain_mask.bit(*follower as usize)
+                        && self.constraints.fits(chain.len() + 1, follower_chars)
+                    {
+                        if let Some(next_state) =
+                            self.constraints.next_state(*states.last().unwrap(), *follower)
+                        {
+                            chain.push(*follower);
+                            states.push(next_state);
+                            chars.push(follower_chars);
+                            chain_mask = chain_mask | U256::one() << *follower;
+                            score += self.weights[*follower as usize];
+
+                            break;
+                        }
+                    } // else: don't break
+                } else {
+                    *follower_index = 0;
+
+                    best_score = cmp::max(best_score, score);
+
+                    if (score, chain.len()) > (local_longest.score, local_longest.words.len())
+                        && self.constraints.accepts(
+                            chain.len(),
+                            chain_mask,
+                            index as u8,
+                            *states.last().unwrap(),
+                        )
+                    {
+                        local_longest = ScoredChain {
+                            words: chain.clone(),
+                            score,
+                        };
+                    }
+
+                    chain.pop();
+                    states.pop();
+                    chars.pop();
+                    score -= self.weights[index];
+
+                    if chain.len() < initial_len {
+                        let local_longest =
+                            self.find_accepted_beginning(&chain, &states, local_longest);
+
+                        return (local_longest, best_score);
+                    }
+
+                    chain_mask = chain_mask & !(U256::one() << index);
 
                     break;
-                } // else: don't break
+                }
+            }
+        }
+    }
+
+    /// The beginnings of an initial chain are chains as well, but no search starts from them.
+    /// Returns the longest beginning of `chain` that satisfies the constraints if it is better
+    /// than `longest`, and `longest` otherwise. Weights can't be negative, so longer beginnings
+    /// are never worse.
+    fn find_accepted_beginning(
+        &self,
+        chain: &[u8],
+        states: &[u16],
+        longest: ScoredChain,
+    ) -> ScoredChain {
+        (1..=chain.len())
+            .rev()
+            .find(|&len| {
+                self.constraints.accepts(
+                    len,
+                    create_chain_mask(&chain[..len]),
+                    chain[len - 1],
+                    states[len - 1],
+                )
+            })
+            .map(|len| ScoredChain {
+                words: chain[..len].to_vec(),
+                score: chain[..len].iter().map(|&w| self.weights[w as usize]).sum(),
+            })
+            .filter(|beginning| beginning.is_better_than(&longest))
+            .unwrap_or(longest)
+    }
+}
+
+/// Walks every chain that starts with a root chain, the same way `find_partial_longest_chain` does,
+/// but without pruning and one chain at a time. Each chain is visited after all chains that
+/// continue it.
+pub struct ChainWalker<'a> {
+    connectivity_index_table: &'a [Vec<u8>],
+    chain: Vec<u8>,
+    chain_mask: U256,
+    follower_table_indices: Vec<u8>,
+    root_len: usize,
+
+    /// The chain was returned by the last call to `next_chain`, so it has to be left before walking on
+    visited: bool,
+}
+
+impl<'a> ChainWalker<'a> {
+    pub fn new(connectivity_index_table: &'a [Vec<u8>], root: Vec<u8>) -> ChainWalker<'a> {
+        debug_assert!(!root.is_empty());
+
+        ChainWalker {
+            connectivity_index_table,
+            chain_mask: create_chain_mask(&root),
+            root_len: root.len(),
+            chain: root,
+            follower_table_indices: vec![0; connectivity_index_table.len()],
+            visited: false,
+        }
+    }
+
+    /// Returns the next chain, or `None` once the root chain was returned
+    pub fn next_chain(&mut self) -> Option<&[u8]> {
+        if self.visited {
+            self.visited = false;
+
+            let index = self.chain.pop().unwrap();
+            self.chain_mask = self.chain_mask & !(U256::one() << index);
+        }
+
+        if self.chain.len() < self.root_len {
+            return None;
+        }
+
+        loop {
+            let index = *self.chain.last().unwrap() as usize;
+
+            let followers = &self.connectivity_index_table[index];
+
+            let follower_index = &mut self.follower_table_indices[index];
+
+            if let Some(&follower) = followers.get(*follower_index as usize) {
+                *follower_index += 1;
+
+                if !self.chain_mask.bit(follower as usize) {
+                    self.chain.push(follower);
+                    self.chain_mask = self.chain_mask | U256::one() << follower;
+                }
             } else {
                 *follower_index = 0;
+                self.visited = true;
 
-                if chain.len() > local_longest.len() {
-                    local_longest = chain.clone();
-                }
+                return Some(&self.chain);
+            }
+        }
+    }
+}
+
+/// Creates a bitmask with one bit set for each word in `chain`
+pub fn create_chain_mask(chain: &[u8]) -> U256 {
+    chain
+        .iter()
+        .fold(U256::zero(), |acc, &i| acc | U256::one() << i)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Mode, Objective, SortedSearchConfig, WordMetadata};
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use words;
+
+    fn random_words(word_count: usize, rng: &mut SmallRng) -> Vec<String> {
+        let mut words = Vec::new();
+
+        while words.len() < word_count {
+            let len = rng.gen_range(2..=4);
+            let word = (0..len)
+                .map(|_| ['a', 'b', 'c'][rng.gen_range(0..3)])
+                .collect::<String>();
+
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+
+        words
+    }
+
+    /// The most characters and the exact number of words of a chain
+    type Bounds = (Option<usize>, Option<usize>);
+
+    fn random_metadata(words: &[String], rng: &mut SmallRng) -> Vec<WordMetadata> {
+        words
+            .iter()
+            .map(|_| WordMetadata {
+                weight: rng.gen_range(1..=5),
+                ..WordMetadata::default()
+            })
+            .collect()
+    }
 
-                chain.pop();
+    /// The best (score, length) of all chains within the bounds, found by trying every chain
+    fn brute_force_best(words: &[String], weights: &[u64], bounds: Bounds) -> Option<(u64, usize)> {
+        fn extend(
+            words: &[String],
+            weights: &[u64],
+            bounds: Bounds,
+            chain: &mut Vec<u8>,
+            best: &mut Option<(u64, usize)>,
+        ) {
+            let (max_chars, word_count) = bounds;
+            let chars = words::pretty_format_index_chain(words, chain)
+                .chars()
+                .count();
 
-                if chain.len() < initial_len {
-                    return (local_longest, estimate_for_initial_chain);
+            if max_chars.is_none_or(|max_chars| chars <= max_chars)
+                && word_count.is_none_or(|word_count| chain.len() == word_count)
+            {
+                let score = chain.iter().map(|&w| weights[w as usize]).sum();
+                *best = (*best).max(Some((score, chain.len())));
+            }
+
+            for next in 0..words.len() as u8 {
+                let linked = chain.last().is_none_or(|&last| {
+                    words::overlapping_chars(&words[last as usize], &words[next as usize]) >= 1
+                });
+
+                if linked && !chain.contains(&next) {
+                    chain.push(next);
+                    extend(words, weights, bounds, chain, best);
+                    chain.pop();
                 }
+            }
+        }
+
+        let mut best = None;
+
+        for start in 0..words.len() as u8 {
+            extend(words, weights, bounds, &mut vec![start], &mut best);
+        }
+
+        best
+    }
+
+    /// Runs sequential, pipelined and quick estimation searches for both objectives and compares
+    /// them to brute force
+    fn assert_matches_brute_force(words: &[String], metadata: &[WordMetadata], bounds: Bounds) {
+        let (max_chars, word_count) = bounds;
+
+        let sequential = SortedSearchConfig {
+            granularity: None,
+            pipeline: None,
+            verbose: false,
+            certificate: false,
+        };
+
+        let pipelined = SortedSearchConfig {
+            granularity: Some(2),
+            pipeline: Some(3),
+            ..sequential
+        };
+
+        for &objective in &[Objective::Length, Objective::Weight] {
+            let weights = metadata
+                .iter()
+                .map(|metadata| match objective {
+                    Objective::Length => 1,
+                    Objective::Weight => metadata.weight as u64,
+                })
+                .collect::<Vec<_>>();
+
+            let expected = brute_force_best(words, &weights, bounds);
+
+            let modes = [
+                Mode::Normal(&sequential),
+                Mode::Normal(&pipelined),
+                Mode::QuickEstimate(&sequential),
+            ];
+
+            for mode in modes {
+                let config = Config {
+                    min_overlap: 1,
+                    metadata: Some(metadata),
+                    pattern: None,
+                    objective,
+                    max_chars,
+                    word_count,
+                    mode,
+                };
+
+                let found = crate::find_longest_chain(words.to_vec(), &config).map(|info| {
+                    let chars = info.chain.chars().count();
+                    assert!(max_chars.is_none_or(|max_chars| chars <= max_chars));
+
+                    (info.score, info.len as usize)
+                });
+
+                assert_eq!(
+                    found.ok(),
+                    expected,
+                    "{:?} {:?} {:?}",
+                    words,
+                    objective,
+                    bounds
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_searches_match_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(45);
+
+        for _ in 0..100 {
+            let words = random_words(rng.gen_range(1..=8), &mut rng);
+            let metadata = random_metadata(&words, &mut rng);
+
+            assert_matches_brute_force(&words, &metadata, (None, None));
+        }
+    }
+
+    #[test]
+    fn bounded_searches_match_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(46);
+
+        for _ in 0..60 {
+            let words = random_words(rng.gen_range(1..=8), &mut rng);
+            let metadata = random_metadata(&words, &mut rng);
 
-                chain_mask = chain_mask & !(U256::one() << index);
+            let bounds = [
+                (Some(rng.gen_range(2..=12)), None),
+                (None, Some(rng.gen_range(1..=5))),
+                (Some(rng.gen_range(4..=12)), Some(rng.gen_range(1..=3))),
+            ];
 
-                break;
+            for &bounds in &bounds {
+                assert_matches_brute_force(&words, &metadata, bounds);
             }
         }
     }
@@ -0,0 +1,285 @@
+use super::decompose::GluedText;
+use super::words::overlapping_chars;
+use crate::{ChainInput, ChainProblem, ChainReport};
+use std::collections::HashSet;
+use std::fmt;
+
+pub fn check_chain(words: &[String], min_overlap: usize, input: &ChainInput) -> ChainReport {
+    let (chain, unsplittable) = match input {
+        ChainInput::Words(chain_words) => (chain_words.to_vec(), None),
+        ChainInput::Glued(text) => split_glued(words, min_overlap, text),
+    };
+
+    let mut problems = Vec::new();
+
+    for (position, word) in chain.iter().enumerate() {
+        if !words.contains(word) {
+            problems.push(ChainProblem::MissingWord {
+                position,
+                word: word.clone(),
+            });
+        } else if let Some(first_position) = chain[..position].iter().position(|w| w == word) {
+            problems.push(ChainProblem::RepeatedWord {
+                position,
+                first_position,
+                word: word.clone(),
+            });
+        }
+
+        if let Some(right) = chain.get(position + 1) {
+            let overlap = overlap(word, right);
+
+            if overlap < min_overlap {
+                problems.push(ChainProblem::BadJunction {
+                    position,
+                    left: word.clone(),
+                    right: right.clone(),
+                    overlap,
+                });
+            }
+        }
+    }
+
+    problems.extend(unsplittable);
+
+    ChainReport {
+        words: chain,
+        problems,
+    }
+}
+
+/// Like `overlapping_chars`, but also accepts the empty words a user might pass in
+fn overlap(left: &str, right: &str) -> usize {
+    if left.is_empty() || right.is_empty() {
+        0
+    } else {
+        overlapping_chars(left, right)
+    }
+}
+
+/// Splits a chain rendered by `pretty_format_index_chain` back into words. If that isn't
+/// possible, returns the split that gets furthest, along with the position where it breaks.
+fn split_glued(
+    words: &[String],
+    min_overlap: usize,
+    text: &str,
+) -> (Vec<String>, Option<ChainProblem>) {
+    let mut splitter = Splitter {
+        glued: GluedText::new(words, text),
+        min_overlap,
+        dead_ends: HashSet::new(),
+        furthest: (0, Vec::new()),
+    };
+
+    let mut chain = Vec::new();
+
+    let split = splitter
+        .glued
+        .first_words()
+        .into_iter()
+        .any(|word| splitter.split_from(0, word, &mut chain));
+
+    if split {
+        (splitter.glued.to_words(&chain), None)
+    } else {
+        let (end, chain) = &splitter.furthest;
+
+        (
+            splitter.glued.to_words(chain),
+            Some(ChainProblem::Unsplittable {
+                offset: splitter.glued.text[..*end].chars().count(),
+            }),
+        )
+    }
+}
+
+struct Splitter<'a> {
+    glued: GluedText<'a>,
+    min_overlap: usize,
+
+    /// (offset, word) pairs from which the rest of the text can't be split
+    dead_ends: HashSet<(usize, usize)>,
+
+    /// The end of the longest prefix that could be split, along with its words
+    furthest: (usize, Vec<usize>),
+}
+
+impl<'a> Splitter<'a> {
+    /// Tries to split the text from `offset` on, starting with `word`, and leaves the words in
+    /// `chain` on success. Unlike a decomposition, this also accepts junctions that overlap by
+    /// too few characters, so they can be reported.
+    fn split_from(&mut self, offset: usize, word: usize, chain: &mut Vec<usize>) -> bool {
+        let end = self.glued.end_of(offset, word);
+
+        chain.push(word);
+
+        if end == self.glued.text.len() {
+            return true;
+        }
+
+        if end > self.furthest.0 {
+            self.furthest = (end, chain.clone());
+        }
+
+        if !self.dead_ends.contains(&(offset, word)) {
+            let mut followers = self.glued.followers(offset, word);
+
+            // Try junctions that are actually allowed first
+            followers.sort_by_key(|&(_, _, overlap)| overlap < self.min_overlap);
+
+            for (next_offset, next, _) in followers {
+                if self.split_from(next_offset, next, chain) {
+                    return true;
+                }
+            }
+
+            self.dead_ends.insert((offset, word));
+        }
+
+        chain.pop();
+
+        false
+    }
+}
+
+impl fmt::Display for ChainProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainProblem::MissingWord { position, word } => {
+                write!(f, "Word {} ({}) is not in the word list.", position + 1, word)
+            }
+            ChainProblem::RepeatedWord {
+                position,
+                first_position,
+                word,
+            } => write!(
+                f,
+                "Word {} ({}) was already used as word {}.",
+                position + 1,
+                word,
+                first_position + 1
+            ),
+            ChainProblem::BadJunction {
+                position,
+                left,
+                right,
+                overlap,
+            } => write!(
+                f,
+                "The chain breaks between words {} and {}: {} and {} only overlap by {} characters.",
+                position + 1,
+                position + 2,
+                left,
+                right,
+                overlap
+            ),
+            ChainProblem::Unsplittable { offset } => write!(
+                f,
+                "The chain breaks at character {}: It can't be continued with words from the list.",
+                offset + 1
+            ),
+        }
+    }
+}
+
+impl ChainReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{verify_chain, ChainInput, ChainProblem, ChainReport};
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|&word| word.to_owned()).collect()
+    }
+
+    fn check_words(chain: &[&str], min_overlap: usize) -> ChainReport {
+        let chain = words(chain);
+        let word_list = words(&["Joshua", "Alexander", "Ryan", "Nicholas", "Andrew"]);
+
+        verify_chain(&word_list, min_overlap, &ChainInput::Words(&chain)).unwrap()
+    }
+
+    fn check_glued(glued: &str, min_overlap: usize) -> ChainReport {
+        let word_list = words(&["Joshua", "Alexander", "Ryan", "Nicholas", "Andrew"]);
+
+        verify_chain(&word_list, min_overlap, &ChainInput::Glued(glued)).unwrap()
+    }
+
+    #[test]
+    fn valid_chains() {
+        assert!(check_words(&["Joshua", "Alexander", "Ryan", "Nicholas"], 1).is_valid());
+
+        let report = check_glued("JoshuAlexandeRyaNicholas", 1);
+        assert!(report.is_valid());
+        assert_eq!(report.words, ["Joshua", "Alexander", "Ryan", "Nicholas"]);
+
+        // Case doesn't matter for glued chains
+        assert!(check_glued("joshualexanderyanicholas", 1).is_valid());
+    }
+
+    #[test]
+    fn problems_are_reported_in_order() {
+        let report = check_words(&["Joshua", "Alexandra", "Ryan", "Nicholas", "Ryan"], 1);
+
+        match &report.problems[..] {
+            [ChainProblem::MissingWord { position: 1, .. }, ChainProblem::BadJunction {
+                position: 1,
+                overlap: 0,
+                ..
+            }, ChainProblem::BadJunction {
+                position: 3,
+                overlap: 0,
+                ..
+            }, ChainProblem::RepeatedWord {
+                position: 4,
+                first_position: 2,
+                ..
+            }] => (),
+            _ => panic!("unexpected problems"),
+        }
+    }
+
+    #[test]
+    fn short_overlaps_are_reported() {
+        let report = check_words(&["Joshua", "Alexander"], 2);
+
+        match &report.problems[..] {
+            [ChainProblem::BadJunction {
+                position: 0,
+                overlap: 1,
+                ..
+            }] => (),
+            _ => panic!("unexpected problems"),
+        }
+
+        // Glued chains are still split, so the short overlap can be reported
+        let report = check_glued("JoshuAlexander", 2);
+        assert_eq!(report.words, ["Joshua", "Alexander"]);
+        assert_eq!(report.problems.len(), 1);
+    }
+
+    #[test]
+    fn glued_chains_report_where_they_break() {
+        let report = check_glued("JoshuAlexandeRyanXYZ", 1);
+
+        assert_eq!(report.words, ["Joshua", "Alexander", "Ryan"]);
+
+        match &report.problems[..] {
+            [ChainProblem::Unsplittable { offset: 17 }] => (),
+            _ => panic!("unexpected problems"),
+        }
+    }
+
+    #[test]
+    fn empty_input_is_an_error() {
+        let word_list = words(&["Joshua"]);
+
+        assert!(verify_chain(&word_list, 1, &ChainInput::Words(&[])).is_err());
+        assert!(verify_chain(&word_list, 1, &ChainInput::Glued("")).is_err());
+        assert!(verify_chain(&[], 1, &ChainInput::Glued("Joshua")).is_err());
+    }
+}
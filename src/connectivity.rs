@@ -1,5 +1,7 @@
 use std::collections::{HashMap,HashSet};
 use super::words::*;
+use super::chain::create_chain_mask;
+use crate::U256;
 
 pub type ConnectivityMap = HashMap<String, HashSet<String>>;
 
@@ -47,8 +49,33 @@ pub fn create_connectivity_index_table(sorted_words: &Vec<String>, connectivity_
             follower_indices.push(index);
         };
 
+        // Followers come out of a HashSet, so fix their order to keep searches reproducible
+        follower_indices.sort_unstable();
+
         table.push(follower_indices);
     };
 
     table
-}
\ No newline at end of file
+}
+
+pub fn create_predecessor_table(connectivity_index_table: &[Vec<u8>]) -> ConnectivityIndexTable {
+
+    let mut table = vec![Vec::new(); connectivity_index_table.len()];
+
+    for (word, followers) in connectivity_index_table.iter().enumerate() {
+
+        for &follower in followers {
+            table[follower as usize].push(word as u8);
+        };
+    };
+
+    table
+}
+
+/// Turns every row of an index table into a bitmask with one bit set for each contained index
+pub fn create_index_masks(table: &[Vec<u8>]) -> Vec<U256> {
+
+    table.iter()
+        .map(|indices| create_chain_mask(indices))
+        .collect()
+}
@@ -0,0 +1,393 @@
+/// Up to this many words, the cover is computed exactly
+pub const EXACT_COVER_LIMIT: usize = 20;
+
+/// Splits all words into as few chains as possible. Returns the chains, longest first, and
+/// whether there can't be a cover with fewer chains.
+pub fn find_min_cover(connectivity_index_table: &[Vec<u8>]) -> (Vec<Vec<u8>>, bool) {
+    let (mut cover, optimal) = if connectivity_index_table.len() <= EXACT_COVER_LIMIT {
+        (find_exact_cover(connectivity_index_table), true)
+    } else {
+        let successors = find_max_matching(connectivity_index_table);
+
+        // Every link in a cover is a matching of words to their followers, and each link saves
+        // one chain, so no cover can do better than the largest matching
+        let lower_bound = successors.iter().filter(|s| s.is_none()).count();
+
+        let cover = find_heuristic_cover(connectivity_index_table, &successors);
+        let optimal = cover.len() == lower_bound;
+
+        (cover, optimal)
+    };
+
+    cover.sort_by_key(|chain| std::cmp::Reverse(chain.len()));
+
+    (cover, optimal)
+}
+
+fn find_exact_cover(connectivity_index_table: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let table = CoverTable::new(connectivity_index_table);
+
+    table.find_chains((1 << connectivity_index_table.len()) - 1)
+}
+
+/// Knows how to split every subset of up to `EXACT_COVER_LIMIT` words into as few chains as
+/// possible. Words are put in an order that breaks into as few chains as possible, where
+/// `covers[mask * n + v]` is the smallest number of chains for the words in `mask` if the last
+/// chain ends with `v`.
+pub struct CoverTable {
+    n: usize,
+    follower_masks: Vec<u32>,
+    covers: Vec<u8>,
+}
+
+impl CoverTable {
+    const UNREACHABLE: u8 = u8::MAX;
+
+    pub fn new(connectivity_index_table: &[Vec<u8>]) -> CoverTable {
+        let n = connectivity_index_table.len();
+
+        debug_assert!(n <= EXACT_COVER_LIMIT);
+
+        let follower_masks = connectivity_index_table
+            .iter()
+            .map(|followers| followers.iter().fold(0u32, |acc, &f| acc | 1 << f))
+            .collect::<Vec<_>>();
+
+        let mut table = CoverTable {
+            n,
+            follower_masks,
+            covers: vec![CoverTable::UNREACHABLE; n << n],
+        };
+
+        for v in 0..n {
+            table.covers[(1 << v) * n + v] = 1;
+        }
+
+        for mask in 1..1usize << n {
+            for v in (0..n).filter(|&v| mask >> v & 1 == 1) {
+                let count = table.covers[mask * n + v];
+
+                if count == CoverTable::UNREACHABLE {
+                    continue;
+                }
+
+                for u in (0..n).filter(|&u| mask >> u & 1 == 0) {
+                    let next = count + table.cost(v, u);
+                    let entry = &mut table.covers[(mask | 1 << u) * n + u];
+                    *entry = (*entry).min(next);
+                }
+            }
+        }
+
+        table
+    }
+
+    /// The smallest number of chains that use exactly the words in `mask`
+    pub fn chain_count(&self, mask: usize) -> u8 {
+        if mask == 0 {
+            return 0;
+        }
+
+        (0..self.n)
+            .map(|v| self.covers[mask * self.n + v])
+            .min()
+            .unwrap()
+    }
+
+    /// Splits the words in `mask` into as few chains as possible
+    pub fn find_chains(&self, mut mask: usize) -> Vec<Vec<u8>> {
+        let n = self.n;
+
+        if mask == 0 {
+            return Vec::new();
+        }
+
+        // Walk back through the table to recover the order of the words
+        let mut last = (0..n).min_by_key(|&v| self.covers[mask * n + v]).unwrap();
+        let mut order = vec![last];
+
+        while mask != 1 << last {
+            let count = self.covers[mask * n + last];
+            let previous_mask = mask & !(1 << last);
+
+            last = (0..n)
+                .filter(|&v| previous_mask >> v & 1 == 1)
+                .find(|&v| {
+                    let previous = self.covers[previous_mask * n + v];
+                    previous != CoverTable::UNREACHABLE && previous + self.cost(v, last) == count
+                })
+                .unwrap();
+
+            mask = previous_mask;
+            order.push(last);
+        }
+
+        order.reverse();
+
+        let mut chains: Vec<Vec<u8>> = Vec::new();
+
+        for (i, &word) in order.iter().enumerate() {
+            if i > 0 && self.cost(order[i - 1], word) == 0 {
+                chains.last_mut().unwrap().push(word as u8);
+            } else {
+                chains.push(vec![word as u8]);
+            }
+        }
+
+        chains
+    }
+
+    /// Whether a new chain has to be started if `to` comes after `from`
+    fn cost(&self, from: usize, to: usize) -> u8 {
+        (self.follower_masks[from] >> to & 1 == 0) as u8
+    }
+}
+
+/// Matches as many words as possible with a follower, such that no follower is used twice.
+/// Returns the matched follower of each word.
+pub fn find_max_matching(connectivity_index_table: &[Vec<u8>]) -> Vec<Option<u8>> {
+    let n = connectivity_index_table.len();
+
+    let mut successors = vec![None; n];
+    let mut predecessors = vec![None; n];
+
+    for word in 0..n {
+        let mut visited = vec![false; n];
+
+        augment(
+            word as u8,
+            connectivity_index_table,
+            &mut visited,
+            &mut successors,
+            &mut predecessors,
+        );
+    }
+
+    successors
+}
+
+/// Looks for an augmenting path that frees up a follower for `word`
+fn augment(
+    word: u8,
+    connectivity_index_table: &[Vec<u8>],
+    visited: &mut [bool],
+    successors: &mut [Option<u8>],
+    predecessors: &mut [Option<u8>],
+) -> bool {
+    for &follower in &connectivity_index_table[word as usize] {
+        if visited[follower as usize] {
+            continue;
+        }
+
+        visited[follower as usize] = true;
+
+        let freed = match predecessors[follower as usize] {
+            None => true,
+            Some(other) => augment(
+                other,
+                connectivity_index_table,
+                visited,
+                successors,
+                predecessors,
+            ),
+        };
+
+        if freed {
+            successors[word as usize] = Some(follower);
+            predecessors[follower as usize] = Some(word);
+
+            return true;
+        }
+    }
+
+    false
+}
+
+/// A matching splits the words into chains and cycles. Cycles are cut open where they can be
+/// attached to a chain, and chains are joined wherever one can follow another.
+fn find_heuristic_cover(
+    connectivity_index_table: &[Vec<u8>],
+    successors: &[Option<u8>],
+) -> Vec<Vec<u8>> {
+    let n = connectivity_index_table.len();
+    let links = |from: u8, to: u8| connectivity_index_table[from as usize].contains(&to);
+
+    let mut has_predecessor = vec![false; n];
+
+    for &successor in successors.iter().flatten() {
+        has_predecessor[successor as usize] = true;
+    }
+
+    let mut visited = vec![false; n];
+
+    let follow = |start: usize, visited: &mut [bool]| {
+        let mut chain = Vec::new();
+        let mut word = Some(start as u8);
+
+        while let Some(w) = word.filter(|&w| !visited[w as usize]) {
+            visited[w as usize] = true;
+            chain.push(w);
+            word = successors[w as usize];
+        }
+
+        chain
+    };
+
+    let mut chains = (0..n)
+        .filter(|&w| !has_predecessor[w])
+        .map(|w| follow(w, &mut visited))
+        .collect::<Vec<_>>();
+
+    // Everything that is left over lies on a cycle
+    for start in 0..n {
+        if visited[start] {
+            continue;
+        }
+
+        let mut cycle = follow(start, &mut visited);
+
+        let attached = chains.iter_mut().any(|chain| {
+            let first = chain[0];
+            let last = *chain.last().unwrap();
+
+            if let Some(i) = cycle.iter().position(|&c| links(last, c)) {
+                cycle.rotate_left(i);
+                chain.extend_from_slice(&cycle);
+                true
+            } else if let Some(i) = cycle.iter().position(|&c| links(c, first)) {
+                cycle.rotate_left(i + 1);
+                chain.splice(0..0, cycle.iter().cloned());
+                true
+            } else {
+                false
+            }
+        });
+
+        if !attached {
+            chains.push(cycle);
+        }
+    }
+
+    // Join chains until none can follow another
+    while let Some((a, b)) = (0..chains.len())
+        .flat_map(|a| (0..chains.len()).map(move |b| (a, b)))
+        .find(|&(a, b)| a != b && links(*chains[a].last().unwrap(), chains[b][0]))
+    {
+        let joined = chains.swap_remove(b);
+
+        // `swap_remove` moved the last chain into the place of `b`
+        let a = if a == chains.len() { b } else { a };
+
+        chains[a].extend(joined);
+    }
+
+    chains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_table(word_count: usize, link_probability: f64, rng: &mut SmallRng) -> Vec<Vec<u8>> {
+        (0..word_count)
+            .map(|u| {
+                (0..word_count as u8)
+                    .filter(|&v| v as usize != u && rng.gen_bool(link_probability))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The smallest number of chains, by trying every order of the words
+    fn brute_force_chain_count(table: &[Vec<u8>]) -> usize {
+        fn extend(table: &[Vec<u8>], order: &mut Vec<u8>, chains: usize, best: &mut usize) {
+            if chains >= *best {
+                return;
+            }
+
+            if order.len() == table.len() {
+                *best = chains;
+                return;
+            }
+
+            for v in 0..table.len() as u8 {
+                if order.contains(&v) {
+                    continue;
+                }
+
+                let new_chain = match order.last() {
+                    Some(&u) => !table[u as usize].contains(&v),
+                    None => true,
+                };
+
+                order.push(v);
+                extend(table, order, chains + new_chain as usize, best);
+                order.pop();
+            }
+        }
+
+        let mut best = table.len();
+        extend(table, &mut Vec::new(), 0, &mut best);
+        best
+    }
+
+    fn assert_valid_cover(table: &[Vec<u8>], cover: &[Vec<u8>]) {
+        let mut words = cover.iter().flatten().cloned().collect::<Vec<_>>();
+        words.sort_unstable();
+
+        assert_eq!(words, (0..table.len() as u8).collect::<Vec<_>>());
+
+        for chain in cover {
+            assert!(chain
+                .windows(2)
+                .all(|pair| table[pair[0] as usize].contains(&pair[1])));
+        }
+
+        assert!(cover.windows(2).all(|pair| pair[0].len() >= pair[1].len()));
+    }
+
+    #[test]
+    fn exact_covers_are_optimal() {
+        let mut rng = SmallRng::seed_from_u64(37);
+
+        for _ in 0..200 {
+            let word_count = rng.gen_range(1..=7);
+            let table = random_table(word_count, rng.gen_range(0.1..0.6), &mut rng);
+
+            let (cover, optimal) = find_min_cover(&table);
+
+            assert!(optimal);
+            assert_valid_cover(&table, &cover);
+            assert_eq!(cover.len(), brute_force_chain_count(&table));
+        }
+    }
+
+    #[test]
+    fn heuristic_covers_are_valid() {
+        let mut rng = SmallRng::seed_from_u64(38);
+
+        for _ in 0..20 {
+            let table = random_table(40, 0.05, &mut rng);
+            let (cover, _) = find_min_cover(&table);
+
+            assert_valid_cover(&table, &cover);
+        }
+
+        // A single chain hidden among a few extra links
+        let mut table = random_table(30, 0.02, &mut rng);
+
+        for (u, followers) in table.iter_mut().enumerate().take(29) {
+            if !followers.contains(&(u as u8 + 1)) {
+                followers.push(u as u8 + 1);
+            }
+        }
+
+        let (cover, optimal) = find_min_cover(&table);
+        assert_valid_cover(&table, &cover);
+
+        assert_eq!(cover.len(), 1);
+        assert!(optimal);
+    }
+}
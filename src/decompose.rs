@@ -0,0 +1,259 @@
+use super::words::overlapping_chars;
+use crate::Decomposition;
+use std::collections::HashMap;
+
+/// Finds all words of a word list that a piece of text starts with. Works on lowercase words and
+/// text, since overlaps ignore case as well.
+pub struct WordTrie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+
+    /// Words ending at this node. There can be more than one if words only differ in case.
+    words: Vec<usize>,
+}
+
+impl WordTrie {
+    pub fn new(lowercase_words: &[String]) -> WordTrie {
+        let mut nodes = vec![TrieNode::default()];
+
+        for (index, word) in lowercase_words.iter().enumerate() {
+            if word.is_empty() {
+                continue;
+            }
+
+            let mut node = 0;
+
+            for &byte in word.as_bytes() {
+                node = match nodes[node].children.get(&byte) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(TrieNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(byte, child);
+                        child
+                    }
+                };
+            }
+
+            nodes[node].words.push(index);
+        }
+
+        WordTrie { nodes }
+    }
+
+    /// Returns the indices of all words that `text` starts with
+    pub fn prefixes_of(&self, text: &[u8]) -> Vec<usize> {
+        let mut prefixes = Vec::new();
+        let mut node = 0;
+
+        for byte in text {
+            node = match self.nodes[node].children.get(byte) {
+                Some(&child) => child,
+                None => break,
+            };
+
+            prefixes.extend(&self.nodes[node].words);
+        }
+
+        prefixes
+    }
+}
+
+/// A glued chain, prepared for splitting it into words. Words are placed at byte offsets into the
+/// lowercase text, and their lengths are taken from the lowercase words, so everything matches up.
+pub struct GluedText<'a> {
+    pub lowercase_words: Vec<String>,
+    pub text: String,
+    trie: WordTrie,
+    words: &'a [String],
+}
+
+impl<'a> GluedText<'a> {
+    pub fn new(words: &'a [String], text: &str) -> GluedText<'a> {
+        let lowercase_words = words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>();
+
+        GluedText {
+            trie: WordTrie::new(&lowercase_words),
+            lowercase_words,
+            text: text.to_lowercase(),
+            words,
+        }
+    }
+
+    /// The words that could start the chain
+    pub fn first_words(&self) -> Vec<usize> {
+        self.trie.prefixes_of(self.text.as_bytes())
+    }
+
+    /// Where `word` ends if it starts at `offset`
+    pub fn end_of(&self, offset: usize, word: usize) -> usize {
+        offset + self.lowercase_words[word].len()
+    }
+
+    /// Returns every (offset, word, overlap) that can follow `word` at `offset` in the text. A
+    /// rendered chain always merges as many characters as possible, so the overlap is exact.
+    pub fn followers(&self, offset: usize, word: usize) -> Vec<(usize, usize, usize)> {
+        let end = self.end_of(offset, word);
+        let left = &self.lowercase_words[word];
+
+        (offset + 1..=end)
+            .flat_map(|next_offset| {
+                self.trie
+                    .prefixes_of(&self.text.as_bytes()[next_offset..])
+                    .into_iter()
+                    .map(move |next| (next_offset, next, end - next_offset))
+            })
+            .filter(|&(_, next, overlap)| {
+                overlapping_chars(left, &self.lowercase_words[next]) == overlap
+            })
+            .collect()
+    }
+
+    pub fn to_words(&self, chain: &[usize]) -> Vec<String> {
+        chain.iter().map(|&i| self.words[i].clone()).collect()
+    }
+}
+
+pub fn decompose(words: &[String], min_overlap: usize, text: &str) -> Vec<Decomposition> {
+    let mut decomposer = Decomposer {
+        glued: GluedText::new(words, text),
+        min_overlap,
+        completable: HashMap::new(),
+        used: vec![false; words.len()],
+        chain: Vec::new(),
+        overlaps: Vec::new(),
+        decompositions: Vec::new(),
+    };
+
+    for word in decomposer.glued.first_words() {
+        decomposer.collect_from(0, word);
+    }
+
+    decomposer.decompositions
+}
+
+struct Decomposer<'a> {
+    glued: GluedText<'a>,
+    min_overlap: usize,
+
+    /// Whether the rest of the text can be split if `word` starts at `offset`, ignoring repeated
+    /// words. Keyed by (offset, word).
+    completable: HashMap<(usize, usize), bool>,
+
+    used: Vec<bool>,
+    chain: Vec<usize>,
+    overlaps: Vec<usize>,
+    decompositions: Vec<Decomposition>,
+}
+
+impl<'a> Decomposer<'a> {
+    /// Collects every decomposition that continues the current chain with `word` at `offset`
+    fn collect_from(&mut self, offset: usize, word: usize) {
+        if self.used[word] || !self.is_completable(offset, word) {
+            return;
+        }
+
+        self.used[word] = true;
+        self.chain.push(word);
+
+        if self.glued.end_of(offset, word) == self.glued.text.len() {
+            self.decompositions.push(Decomposition {
+                words: self.glued.to_words(&self.chain),
+                overlaps: self.overlaps.clone(),
+            });
+        } else {
+            for (next_offset, next, overlap) in self.allowed_followers(offset, word) {
+                self.overlaps.push(overlap);
+                self.collect_from(next_offset, next);
+                self.overlaps.pop();
+            }
+        }
+
+        self.chain.pop();
+        self.used[word] = false;
+    }
+
+    fn is_completable(&mut self, offset: usize, word: usize) -> bool {
+        if let Some(&completable) = self.completable.get(&(offset, word)) {
+            return completable;
+        }
+
+        let completable = self.glued.end_of(offset, word) == self.glued.text.len()
+            || self
+                .allowed_followers(offset, word)
+                .into_iter()
+                .any(|(next_offset, next, _)| self.is_completable(next_offset, next));
+
+        self.completable.insert((offset, word), completable);
+
+        completable
+    }
+
+    fn allowed_followers(&self, offset: usize, word: usize) -> Vec<(usize, usize, usize)> {
+        let mut followers = self.glued.followers(offset, word);
+        followers.retain(|&(_, _, overlap)| overlap >= self.min_overlap);
+        followers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompose;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|&word| word.to_owned()).collect()
+    }
+
+    fn decompositions(word_list: &[&str], min_overlap: usize, glued: &str) -> Vec<Vec<String>> {
+        decompose(&words(word_list), min_overlap, glued)
+            .into_iter()
+            .map(|decomposition| decomposition.words)
+            .collect()
+    }
+
+    #[test]
+    fn unique_decomposition() {
+        let word_list = words(&["Joshua", "Alexander", "Ryan", "Nicholas"]);
+        let found = decompose(&word_list, 1, "JoshuAlexandeRyaNicholas");
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].words, ["Joshua", "Alexander", "Ryan", "Nicholas"]);
+        assert_eq!(found[0].overlaps, [1, 1, 1]);
+    }
+
+    #[test]
+    fn ambiguous_decomposition() {
+        let mut found = decompositions(&["abc", "bcd", "cde"], 1, "abcde");
+        found.sort();
+
+        assert_eq!(
+            found,
+            [words(&["abc", "bcd", "cde"]), words(&["abc", "cde"])]
+        );
+
+        // The second split only overlaps by one character
+        assert_eq!(
+            decompositions(&["abc", "bcd", "cde"], 2, "abcde"),
+            [words(&["abc", "bcd", "cde"])]
+        );
+    }
+
+    #[test]
+    fn words_are_not_repeated() {
+        assert!(decompositions(&["ab", "ba"], 1, "abab").is_empty());
+        assert_eq!(
+            decompositions(&["ab", "ba"], 1, "aba"),
+            [words(&["ab", "ba"])]
+        );
+    }
+
+    #[test]
+    fn unsplittable_text_has_no_decomposition() {
+        assert!(decompositions(&["Joshua", "Alexander"], 1, "JoshuAlexandra").is_empty());
+        assert!(decompositions(&["Joshua", "Alexander"], 1, "XJoshua").is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+use super::chain::{create_chain_mask, ChainWalker};
+use super::connectivity;
+use crate::{ChainEnumeration, ChainFilter, Chains};
+use rayon::prelude::*;
+
+/// Chains with this many words are walked as separate tasks by the parallel functions. All
+/// shorter chains are checked right away.
+pub const TASK_DEPTH: usize = 3;
+
+impl ChainEnumeration {
+    pub fn new(
+        words: Vec<String>,
+        connectivity_index_table: Vec<Vec<u8>>,
+        filter: ChainFilter,
+    ) -> ChainEnumeration {
+        let follower_masks = connectivity::create_index_masks(&connectivity_index_table);
+
+        let predecessor_masks = connectivity::create_index_masks(
+            &connectivity::create_predecessor_table(&connectivity_index_table),
+        );
+
+        ChainEnumeration {
+            words,
+            connectivity_index_table,
+            follower_masks,
+            predecessor_masks,
+            filter,
+        }
+    }
+
+    /// Walks the chains one after another. Each start word is finished before the next one, and
+    /// every chain comes after all chains that continue it.
+    pub fn iter(&self) -> Chains<'_> {
+        Chains {
+            enumeration: self,
+            next_start: 0,
+            walker: None,
+        }
+    }
+
+    /// Calls `f` with the words of every chain, from as many threads as there are cores. The
+    /// chains come in no particular order.
+    pub fn par_for_each<F>(&self, f: F)
+    where
+        F: Fn(Vec<String>) + Sync + Send,
+    {
+        let (short_chains, roots) = self.split_chains_from_all_words();
+
+        short_chains
+            .into_par_iter()
+            .filter(|chain| self.matches(chain))
+            .for_each(|chain| f(self.to_words(&chain)));
+
+        roots.into_par_iter().for_each(|root| {
+            let mut walker = ChainWalker::new(&self.connectivity_index_table, root);
+
+            while let Some(chain) = walker.next_chain() {
+                if self.matches(chain) {
+                    f(self.to_words(chain));
+                }
+            }
+        });
+    }
+
+    /// Counts the chains in parallel, without building their words
+    pub fn count(&self) -> u64 {
+        let (short_chains, roots) = self.split_chains_from_all_words();
+
+        let short_count = short_chains
+            .iter()
+            .filter(|chain| self.matches(chain))
+            .count() as u64;
+
+        let long_count = roots
+            .into_par_iter()
+            .map(|root| {
+                let mut walker = ChainWalker::new(&self.connectivity_index_table, root);
+                let mut count = 0;
+
+                while let Some(chain) = walker.next_chain() {
+                    if self.matches(chain) {
+                        count += 1;
+                    }
+                }
+
+                count
+            })
+            .sum::<u64>();
+
+        short_count + long_count
+    }
+
+    fn matches(&self, chain: &[u8]) -> bool {
+        if chain.len() < self.filter.min_len {
+            return false;
+        }
+
+        if !self.filter.maximal {
+            return true;
+        }
+
+        let chain_mask = create_chain_mask(chain);
+
+        let first = chain[0] as usize;
+        let last = chain[chain.len() - 1] as usize;
+
+        (self.predecessor_masks[first] & !chain_mask).is_zero()
+            && (self.follower_masks[last] & !chain_mask).is_zero()
+    }
+
+    fn to_words(&self, chain: &[u8]) -> Vec<String> {
+        chain
+            .iter()
+            .map(|&w| self.words[w as usize].clone())
+            .collect()
+    }
+
+    fn split_chains_from_all_words(&self) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let start_words = (0..self.words.len()).map(|w| w as u8).collect::<Vec<_>>();
+
+        split_chains(&self.connectivity_index_table, &start_words, TASK_DEPTH)
+    }
+}
+
+/// Splits the chains that begin with one of `start_words` into all chains with fewer than `depth`
+/// words, and the chains with exactly that many words. Walking from each of the latter finds every
+/// longer chain once.
+pub fn split_chains(
+    connectivity_index_table: &[Vec<u8>],
+    start_words: &[u8],
+    depth: usize,
+) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let table = connectivity_index_table;
+
+    let mut short_chains = Vec::new();
+    let mut level = start_words.iter().map(|&w| vec![w]).collect::<Vec<_>>();
+
+    for _ in 1..depth {
+        let next_level = level
+            .iter()
+            .flat_map(|chain| {
+                table[*chain.last().unwrap() as usize]
+                    .iter()
+                    .filter(move |follower| !chain.contains(follower))
+                    .map(move |&follower| {
+                        let mut next = chain.clone();
+                        next.push(follower);
+                        next
+                    })
+            })
+            .collect();
+
+        short_chains.append(&mut level);
+        level = next_level;
+    }
+
+    (short_chains, level)
+}
+
+impl<'a> Iterator for Chains<'a> {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        let enumeration = self.enumeration;
+
+        loop {
+            if let Some(walker) = &mut self.walker {
+                while let Some(chain) = walker.next_chain() {
+                    if enumeration.matches(chain) {
+                        return Some(enumeration.to_words(chain));
+                    }
+                }
+            }
+
+            if self.next_start >= enumeration.words.len() {
+                return None;
+            }
+
+            self.walker = Some(ChainWalker::new(
+                &enumeration.connectivity_index_table,
+                vec![self.next_start as u8],
+            ));
+
+            self.next_start += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    fn random_table(word_count: usize, link_probability: f64, rng: &mut SmallRng) -> Vec<Vec<u8>> {
+        (0..word_count)
+            .map(|u| {
+                (0..word_count as u8)
+                    .filter(|&v| v as usize != u && rng.gen_bool(link_probability))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn enumeration(table: &[Vec<u8>], min_len: usize, maximal: bool) -> ChainEnumeration {
+        let words = (0..table.len()).map(|w| w.to_string()).collect();
+
+        ChainEnumeration::new(words, table.to_vec(), ChainFilter { min_len, maximal })
+    }
+
+    /// Every chain that passes the filter, found by extending every chain by every word
+    fn brute_force_chains(table: &[Vec<u8>], min_len: usize, maximal: bool) -> Vec<Vec<u8>> {
+        fn extend(table: &[Vec<u8>], chain: &mut Vec<u8>, chains: &mut Vec<Vec<u8>>) {
+            chains.push(chain.clone());
+
+            for v in 0..table.len() as u8 {
+                if table[*chain.last().unwrap() as usize].contains(&v) && !chain.contains(&v) {
+                    chain.push(v);
+                    extend(table, chain, chains);
+                    chain.pop();
+                }
+            }
+        }
+
+        let mut chains = Vec::new();
+
+        for w in 0..table.len() as u8 {
+            extend(table, &mut vec![w], &mut chains);
+        }
+
+        let extendable = |chain: &Vec<u8>| {
+            (0..table.len() as u8).any(|v| {
+                !chain.contains(&v)
+                    && (table[v as usize].contains(&chain[0])
+                        || table[*chain.last().unwrap() as usize].contains(&v))
+            })
+        };
+
+        chains
+            .into_iter()
+            .filter(|chain| chain.len() >= min_len && !(maximal && extendable(chain)))
+            .collect()
+    }
+
+    fn to_words(chain: &[u8]) -> Vec<String> {
+        chain.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn all_ways_match_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(47);
+
+        for _ in 0..100 {
+            let word_count = rng.gen_range(1..=8);
+            let table = random_table(word_count, rng.gen_range(0.1..0.6), &mut rng);
+            let min_len = rng.gen_range(0..=4);
+            let maximal = rng.gen_bool(0.5);
+
+            let mut expected = brute_force_chains(&table, min_len, maximal)
+                .iter()
+                .map(|chain| to_words(chain))
+                .collect::<Vec<_>>();
+            expected.sort();
+
+            let enumeration = enumeration(&table, min_len, maximal);
+
+            let mut walked = enumeration.iter().collect::<Vec<_>>();
+            walked.sort();
+            assert_eq!(walked, expected);
+
+            let collected = Mutex::new(Vec::new());
+            enumeration.par_for_each(|chain| collected.lock().unwrap().push(chain));
+            let mut collected = collected.into_inner().unwrap();
+            collected.sort();
+            assert_eq!(collected, expected);
+
+            assert_eq!(enumeration.count(), expected.len() as u64);
+        }
+    }
+
+    #[test]
+    fn chains_are_walked_lazily_and_in_order() {
+        // Every word can follow every other one, which makes for 12! chains of all words
+        let table = (0..12u8)
+            .map(|u| (0..12).filter(|&v| v != u).collect())
+            .collect::<Vec<_>>();
+
+        let first = enumeration(&table, 12, false)
+            .iter()
+            .take(3)
+            .collect::<Vec<_>>();
+
+        assert_eq!(first[0], to_words(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]));
+        assert_eq!(first.len(), 3);
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), 3);
+        assert!(first.iter().all(|chain| chain[0] == "0"));
+    }
+
+    #[test]
+    fn split_chains_cover_every_chain_once() {
+        let mut rng = SmallRng::seed_from_u64(470);
+        let table = random_table(9, 0.4, &mut rng);
+        let start_words = (0..9).collect::<Vec<_>>();
+
+        for depth in 1..=4 {
+            let (short_chains, roots) = split_chains(&table, &start_words, depth);
+
+            assert!(short_chains.iter().all(|chain| chain.len() < depth));
+            assert!(roots.iter().all(|chain| chain.len() == depth));
+
+            let mut chains = short_chains;
+
+            for root in roots {
+                let mut walker = ChainWalker::new(&table, root);
+
+                while let Some(chain) = walker.next_chain() {
+                    chains.push(chain.to_vec());
+                }
+            }
+
+            let mut expected = brute_force_chains(&table, 0, false);
+
+            chains.sort();
+            expected.sort();
+            assert_eq!(chains, expected, "depth {}", depth);
+        }
+    }
+}
@@ -0,0 +1,861 @@
+use crate::ExportFormat;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// How many terms are written on a single line of an LP file
+const LP_TERMS_PER_LINE: usize = 8;
+
+/// Words are referred to by their index in the connectivity index table, so solutions can only be
+/// read back with the same table
+pub fn write_model<W: Write>(
+    connectivity_index_table: &[Vec<u8>],
+    format: &ExportFormat,
+    writer: &mut W,
+) -> io::Result<()> {
+    match *format {
+        ExportFormat::Lp => write_lp(&create_linear_model(connectivity_index_table), writer),
+        ExportFormat::Mps => write_mps(&create_linear_model(connectivity_index_table), writer),
+        ExportFormat::Cnf { min_len } => write_dimacs(
+            &create_sat_model(connectivity_index_table, Some(min_len)),
+            writer,
+        ),
+        ExportFormat::Wcnf => {
+            write_dimacs(&create_sat_model(connectivity_index_table, None), writer)
+        }
+    }
+}
+
+pub fn read_solution(
+    connectivity_index_table: &[Vec<u8>],
+    format: &ExportFormat,
+    solution: &str,
+) -> Result<Vec<u8>, &'static str> {
+    let chain = match *format {
+        ExportFormat::Lp | ExportFormat::Mps => {
+            read_linear_solution(connectivity_index_table.len(), solution)?
+        }
+        ExportFormat::Cnf { .. } | ExportFormat::Wcnf => {
+            read_sat_solution(connectivity_index_table.len(), solution)?
+        }
+    };
+
+    if chain.is_empty() {
+        return Err("The solution does not contain any words.");
+    }
+
+    let mut used = vec![false; connectivity_index_table.len()];
+
+    for &word in &chain {
+        if std::mem::replace(&mut used[word as usize], true) {
+            return Err("The solution uses a word more than once.");
+        }
+    }
+
+    let linked = chain
+        .windows(2)
+        .all(|pair| connectivity_index_table[pair[0] as usize].contains(&pair[1]));
+
+    if !linked {
+        return Err("The solution contains words that don't overlap.");
+    }
+
+    Ok(chain)
+}
+
+// Integer linear program
+//
+// x_v: word v is part of the chain
+// s_v, t_v: word v is the first/last word of the chain
+// y_u_v: word v directly follows word u
+// o_v: position of word v in the chain. These enforce an ordering along every used link, which
+//      rules out cycles (Miller-Tucker-Zemlin constraints).
+
+enum Sense {
+    Equal,
+    LessOrEqual,
+}
+
+struct LinearConstraint {
+    name: String,
+    terms: Vec<(String, i64)>,
+    sense: Sense,
+    rhs: i64,
+}
+
+struct LinearModel {
+    /// Maximized
+    objective: Vec<(String, i64)>,
+    constraints: Vec<LinearConstraint>,
+    binaries: Vec<String>,
+    /// Integer or continuous variables with their upper bound. The lower bound is always 0.
+    bounded: Vec<(String, i64)>,
+}
+
+fn create_linear_model(connectivity_index_table: &[Vec<u8>]) -> LinearModel {
+    let n = connectivity_index_table.len() as i64;
+    let words = 0..connectivity_index_table.len();
+
+    let x = |v: usize| format!("x_{}", v);
+    let s = |v: usize| format!("s_{}", v);
+    let t = |v: usize| format!("t_{}", v);
+    let y = |u: usize, v: u8| format!("y_{}_{}", u, v);
+    let o = |v: usize| format!("o_{}", v);
+
+    let mut predecessors = vec![Vec::new(); connectivity_index_table.len()];
+
+    for (u, followers) in connectivity_index_table.iter().enumerate() {
+        for &v in followers {
+            predecessors[v as usize].push(u);
+        }
+    }
+
+    let mut constraints = vec![
+        LinearConstraint {
+            name: "start".to_owned(),
+            terms: words.clone().map(|v| (s(v), 1)).collect(),
+            sense: Sense::Equal,
+            rhs: 1,
+        },
+        LinearConstraint {
+            name: "end".to_owned(),
+            terms: words.clone().map(|v| (t(v), 1)).collect(),
+            sense: Sense::Equal,
+            rhs: 1,
+        },
+    ];
+
+    for v in words.clone() {
+        // A used word is entered exactly once, either as the start or through a link
+        let mut terms = predecessors[v]
+            .iter()
+            .map(|&u| (y(u, v as u8), 1))
+            .collect::<Vec<_>>();
+        terms.push((s(v), 1));
+        terms.push((x(v), -1));
+
+        constraints.push(LinearConstraint {
+            name: format!("in_{}", v),
+            terms,
+            sense: Sense::Equal,
+            rhs: 0,
+        });
+
+        // ... and left exactly once, either as the end or through a link
+        let mut terms = connectivity_index_table[v]
+            .iter()
+            .map(|&w| (y(v, w), 1))
+            .collect::<Vec<_>>();
+        terms.push((t(v), 1));
+        terms.push((x(v), -1));
+
+        constraints.push(LinearConstraint {
+            name: format!("out_{}", v),
+            terms,
+            sense: Sense::Equal,
+            rhs: 0,
+        });
+    }
+
+    for (u, followers) in connectivity_index_table.iter().enumerate() {
+        for &v in followers {
+            // o_v >= o_u + 1 if the link is used
+            constraints.push(LinearConstraint {
+                name: format!("order_{}_{}", u, v),
+                terms: vec![(o(u), 1), (o(v as usize), -1), (y(u, v), n)],
+                sense: Sense::LessOrEqual,
+                rhs: n - 1,
+            });
+        }
+    }
+
+    let mut binaries = Vec::new();
+
+    for v in words.clone() {
+        binaries.push(x(v));
+        binaries.push(s(v));
+        binaries.push(t(v));
+    }
+
+    for (u, followers) in connectivity_index_table.iter().enumerate() {
+        binaries.extend(followers.iter().map(|&v| y(u, v)));
+    }
+
+    LinearModel {
+        objective: words.clone().map(|v| (x(v), 1)).collect(),
+        constraints,
+        binaries,
+        bounded: words.map(|v| (o(v), n - 1)).collect(),
+    }
+}
+
+fn write_lp<W: Write>(model: &LinearModel, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "\\ Longest chain of overlapping words")?;
+    writeln!(writer, "Maximize")?;
+    write_lp_terms(" obj:", &model.objective, writer)?;
+
+    writeln!(writer, "Subject To")?;
+
+    for constraint in &model.constraints {
+        write_lp_terms(&format!(" {}:", constraint.name), &constraint.terms, writer)?;
+
+        let sense = match constraint.sense {
+            Sense::Equal => "=",
+            Sense::LessOrEqual => "<=",
+        };
+
+        writeln!(writer, "   {} {}", sense, constraint.rhs)?;
+    }
+
+    writeln!(writer, "Bounds")?;
+
+    for (name, upper) in &model.bounded {
+        writeln!(writer, " 0 <= {} <= {}", name, upper)?;
+    }
+
+    writeln!(writer, "Binary")?;
+
+    for names in model.binaries.chunks(LP_TERMS_PER_LINE) {
+        writeln!(writer, " {}", names.join(" "))?;
+    }
+
+    writeln!(writer, "End")
+}
+
+fn write_lp_terms<W: Write>(
+    label: &str,
+    terms: &[(String, i64)],
+    writer: &mut W,
+) -> io::Result<()> {
+    write!(writer, "{}", label)?;
+
+    for (i, (name, coefficient)) in terms.iter().enumerate() {
+        if i > 0 && i % LP_TERMS_PER_LINE == 0 {
+            write!(writer, "\n  ")?;
+        }
+
+        let sign = if *coefficient < 0 { '-' } else { '+' };
+
+        match coefficient.abs() {
+            1 => write!(writer, " {} {}", sign, name)?,
+            c => write!(writer, " {} {} {}", sign, c, name)?,
+        }
+    }
+
+    writeln!(writer)
+}
+
+/// Writes free MPS. Since not all solvers understand OBJSENSE, the negated objective is minimized.
+fn write_mps<W: Write>(model: &LinearModel, writer: &mut W) -> io::Result<()> {
+    // Column-wise view of the model, in the order of first appearance
+    let mut columns: Vec<(&str, Vec<(&str, i64)>)> = Vec::new();
+    let mut column_indices: HashMap<&str, usize> = HashMap::new();
+
+    let objective = model.objective.iter().map(|(name, c)| ("obj", name, -c));
+    let rows = model.constraints.iter().flat_map(|constraint| {
+        constraint
+            .terms
+            .iter()
+            .map(move |(name, c)| (constraint.name.as_str(), name, *c))
+    });
+
+    for (row, name, coefficient) in objective.chain(rows) {
+        let index = *column_indices.entry(name).or_insert_with(|| {
+            columns.push((name, Vec::new()));
+            columns.len() - 1
+        });
+
+        columns[index].1.push((row, coefficient));
+    }
+
+    writeln!(
+        writer,
+        "* Longest chain of overlapping words (negated objective)"
+    )?;
+    writeln!(writer, "NAME wordchain")?;
+    writeln!(writer, "ROWS")?;
+    writeln!(writer, " N obj")?;
+
+    for constraint in &model.constraints {
+        let sense = match constraint.sense {
+            Sense::Equal => 'E',
+            Sense::LessOrEqual => 'L',
+        };
+
+        writeln!(writer, " {} {}", sense, constraint.name)?;
+    }
+
+    writeln!(writer, "COLUMNS")?;
+
+    for (name, entries) in &columns {
+        for (row, coefficient) in entries {
+            writeln!(writer, "    {} {} {}", name, row, coefficient)?;
+        }
+    }
+
+    writeln!(writer, "RHS")?;
+
+    for constraint in model.constraints.iter().filter(|c| c.rhs != 0) {
+        writeln!(writer, "    RHS {} {}", constraint.name, constraint.rhs)?;
+    }
+
+    writeln!(writer, "BOUNDS")?;
+
+    for name in &model.binaries {
+        writeln!(writer, " BV BND {}", name)?;
+    }
+
+    for (name, upper) in &model.bounded {
+        writeln!(writer, " UP BND {} {}", name, upper)?;
+    }
+
+    writeln!(writer, "ENDATA")
+}
+
+/// Reads any solution file that lists variables by name, followed by their value somewhere on
+/// the same line. This covers the output of most common solvers.
+fn read_linear_solution(word_count: usize, solution: &str) -> Result<Vec<u8>, &'static str> {
+    let mut starts = Vec::new();
+    let mut links = Vec::new();
+
+    for line in solution.lines() {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        for (i, token) in tokens.iter().enumerate() {
+            let value = match tokens[i + 1..].iter().find_map(|t| t.parse::<f64>().ok()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if value < 0.5 {
+                continue;
+            }
+
+            let mut parts = token.split('_');
+
+            let indices = parts
+                .clone()
+                .skip(1)
+                .map(|p| p.parse::<usize>().ok().filter(|&i| i < word_count))
+                .collect::<Option<Vec<_>>>();
+
+            match (parts.next(), indices.as_deref()) {
+                (Some("s"), Some(&[v])) => starts.push(v),
+                (Some("y"), Some(&[u, v])) => links.push((u, v)),
+                _ => (),
+            }
+        }
+    }
+
+    let mut current = match starts[..] {
+        [start] => start,
+        [] => return Err("The solution has no start word."),
+        _ => return Err("The solution has more than one start word."),
+    };
+
+    let mut next = HashMap::new();
+
+    for (u, v) in links {
+        if next.insert(u, v).is_some() {
+            return Err("The solution has a word with more than one follower.");
+        }
+    }
+
+    let mut chain = Vec::new();
+
+    loop {
+        chain.push(current as u8);
+
+        if chain.len() > word_count {
+            return Err("The solution contains a cycle.");
+        }
+
+        match next.get(&current) {
+            Some(&v) => current = v,
+            None => break,
+        }
+    }
+
+    Ok(chain)
+}
+
+// Satisfiability problem
+//
+// p(v, k): word v is at position k of the chain
+// o(k): position k of the chain is occupied
+//
+// Occupied positions form a prefix, every position holds at most one word, every word is used at
+// most once, and the word at each occupied position (except the last) is followed by one of its
+// followers. The CNF requires position `min_len - 1` to be occupied, whereas the WCNF rewards
+// every occupied position with a soft clause.
+
+struct SatModel {
+    variable_count: usize,
+    hard: Vec<Vec<i64>>,
+    soft: Vec<Vec<i64>>,
+}
+
+fn position_variable(word_count: usize, v: usize, k: usize) -> i64 {
+    (k * word_count + v + 1) as i64
+}
+
+fn occupied_variable(word_count: usize, k: usize) -> i64 {
+    (word_count * word_count + k + 1) as i64
+}
+
+fn create_sat_model(connectivity_index_table: &[Vec<u8>], min_len: Option<usize>) -> SatModel {
+    let n = connectivity_index_table.len();
+    let p = |v: usize, k: usize| position_variable(n, v, k);
+    let o = |k: usize| occupied_variable(n, k);
+
+    let mut model = SatModel {
+        variable_count: n * n + n,
+        hard: vec![vec![o(0)]],
+        soft: Vec::new(),
+    };
+
+    for k in 0..n {
+        // Occupied means exactly one word in this position
+        let mut any_word = vec![-o(k)];
+
+        for v in 0..n {
+            model.hard.push(vec![-p(v, k), o(k)]);
+            any_word.push(p(v, k));
+        }
+
+        model.hard.push(any_word);
+        add_at_most_one(&mut model, (0..n).map(|v| p(v, k)).collect());
+
+        if k + 1 < n {
+            model.hard.push(vec![-o(k + 1), o(k)]);
+
+            for (u, followers) in connectivity_index_table.iter().enumerate() {
+                let mut next_is_follower = vec![-p(u, k), -o(k + 1)];
+                next_is_follower.extend(followers.iter().map(|&v| p(v as usize, k + 1)));
+
+                model.hard.push(next_is_follower);
+            }
+        }
+    }
+
+    for v in 0..n {
+        add_at_most_one(&mut model, (0..n).map(|k| p(v, k)).collect());
+    }
+
+    match min_len {
+        Some(min_len) => model.hard.push(vec![o(min_len.clamp(1, n) - 1)]),
+        None => model.soft.extend((1..n).map(|k| vec![o(k)])),
+    }
+
+    model
+}
+
+/// Sequential counter encoding, which needs linearly many clauses instead of quadratically many
+fn add_at_most_one(model: &mut SatModel, literals: Vec<i64>) {
+    if literals.len() < 2 {
+        return;
+    }
+
+    let first_counter = model.variable_count as i64 + 1;
+    let counter = |i: usize| first_counter + i as i64;
+    let last = literals.len() - 1;
+
+    model.variable_count += last;
+
+    model.hard.push(vec![-literals[0], counter(0)]);
+
+    for (i, &literal) in literals.iter().enumerate().take(last).skip(1) {
+        model.hard.push(vec![-literal, counter(i)]);
+        model.hard.push(vec![-counter(i - 1), counter(i)]);
+        model.hard.push(vec![-literal, -counter(i - 1)]);
+    }
+
+    model.hard.push(vec![-literals[last], -counter(last - 1)]);
+}
+
+/// Writes CNF if there are no soft clauses, and WCNF (with unit weights) otherwise
+fn write_dimacs<W: Write>(model: &SatModel, writer: &mut W) -> io::Result<()> {
+    let clause_count = model.hard.len() + model.soft.len();
+
+    writeln!(writer, "c Longest chain of overlapping words")?;
+
+    let hard_prefix = if model.soft.is_empty() {
+        writeln!(writer, "p cnf {} {}", model.variable_count, clause_count)?;
+        String::new()
+    } else {
+        let top = model.soft.len() + 1;
+        writeln!(
+            writer,
+            "p wcnf {} {} {}",
+            model.variable_count, clause_count, top
+        )?;
+        format!("{} ", top)
+    };
+
+    for clause in &model.hard {
+        write_dimacs_clause(&hard_prefix, clause, writer)?;
+    }
+
+    for clause in &model.soft {
+        write_dimacs_clause("1 ", clause, writer)?;
+    }
+
+    Ok(())
+}
+
+fn write_dimacs_clause<W: Write>(prefix: &str, clause: &[i64], writer: &mut W) -> io::Result<()> {
+    write!(writer, "{}", prefix)?;
+
+    for literal in clause {
+        write!(writer, "{} ", literal)?;
+    }
+
+    writeln!(writer, "0")
+}
+
+/// Reads the variable assignment from either competition style output ("v" lines) or a plain
+/// list of literals, which MiniSat writes after a line with just the result
+fn read_sat_solution(word_count: usize, solution: &str) -> Result<Vec<u8>, &'static str> {
+    const NO_SOLUTION: &str = "The solver did not find a solution.";
+
+    let mut positions = vec![None; word_count];
+
+    for line in solution.lines() {
+        let line = line.trim();
+
+        let literals = match line.chars().next() {
+            Some('v') => &line[1..],
+            Some('s') if line.contains("UNSAT") => return Err(NO_SOLUTION),
+            Some('c') | Some('s') | Some('o') | None => continue,
+            _ => match line {
+                "UNSAT" => return Err(NO_SOLUTION),
+                "SAT" | "INDET" => continue,
+                _ => line,
+            },
+        };
+
+        for literal in literals.split_whitespace() {
+            let literal = literal
+                .parse::<i64>()
+                .map_err(|_| "The solution contains an invalid literal.")?;
+
+            if literal <= 0 || literal > (word_count * word_count) as i64 {
+                continue;
+            }
+
+            let variable = (literal - 1) as usize;
+            let (k, v) = (variable / word_count, variable % word_count);
+
+            if positions[k].replace(v as u8).is_some() {
+                return Err("The solution has more than one word in the same position.");
+            }
+        }
+    }
+
+    let chain = positions
+        .iter()
+        .take_while(|word| word.is_some())
+        .map(|word| word.unwrap())
+        .collect::<Vec<_>>();
+
+    if positions[chain.len()..].iter().any(Option::is_some) {
+        return Err("The solution has gaps between its words.");
+    }
+
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The longest chain is 2 0 1 3. Word 4 can only be followed by 3, and 0 1 2 form a cycle.
+    fn connectivity_index_table() -> Vec<Vec<u8>> {
+        vec![vec![1], vec![2, 3], vec![0], vec![], vec![3]]
+    }
+
+    fn export(format: &ExportFormat) -> String {
+        let mut model = Vec::new();
+        write_model(&connectivity_index_table(), format, &mut model).unwrap();
+        String::from_utf8(model).unwrap()
+    }
+
+    /// Returns the variable count, the hard clauses and the soft clauses of a CNF or WCNF file
+    fn read_dimacs(text: &str) -> (usize, Vec<Vec<i64>>, Vec<Vec<i64>>) {
+        let mut variable_count = 0;
+        let mut top = None;
+        let (mut hard, mut soft) = (Vec::new(), Vec::new());
+
+        for line in text.lines() {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+            match tokens[0] {
+                "c" => (),
+                "p" => {
+                    variable_count = tokens[2].parse().unwrap();
+                    top = tokens.get(4).map(|top| top.parse::<i64>().unwrap());
+                }
+                _ => {
+                    let mut numbers = tokens.iter().map(|t| t.parse::<i64>().unwrap());
+
+                    let weight = top.map(|_| numbers.next().unwrap());
+                    let clause = numbers.take_while(|&literal| literal != 0).collect();
+
+                    if weight.is_none() || weight == top {
+                        hard.push(clause);
+                    } else {
+                        soft.push(clause);
+                    }
+                }
+            }
+        }
+
+        (variable_count, hard, soft)
+    }
+
+    /// A small DPLL solver. Returns the assignment as a list of literals.
+    fn solve(variable_count: usize, clauses: &[Vec<i64>]) -> Option<Vec<i64>> {
+        let mut values = vec![0i64; variable_count + 1];
+
+        if search(clauses, &mut values) {
+            Some(
+                (1..=variable_count as i64)
+                    .map(|v| if values[v as usize] > 0 { v } else { -v })
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn search(clauses: &[Vec<i64>], values: &mut Vec<i64>) -> bool {
+        let saved = values.clone();
+        let value = |values: &[i64], literal: i64| {
+            values[literal.unsigned_abs() as usize] * literal.signum()
+        };
+
+        // Unit propagation
+        loop {
+            let mut changed = false;
+
+            for clause in clauses {
+                if clause.iter().any(|&literal| value(values, literal) > 0) {
+                    continue;
+                }
+
+                let mut open = clause
+                    .iter()
+                    .filter(|&&literal| value(values, literal) == 0);
+
+                match (open.next(), open.next()) {
+                    (None, _) => {
+                        *values = saved;
+                        return false;
+                    }
+                    (Some(&literal), None) => {
+                        values[literal.unsigned_abs() as usize] = literal.signum();
+                        changed = true;
+                    }
+                    _ => (),
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let variable = match (1..values.len()).find(|&v| values[v] == 0) {
+            Some(variable) => variable,
+            None => return true,
+        };
+
+        for &value in &[1, -1] {
+            values[variable] = value;
+
+            if search(clauses, values) {
+                return true;
+            }
+        }
+
+        *values = saved;
+        false
+    }
+
+    /// Writes an assignment the way MiniSat does and reads it back
+    fn import_sat(format: &ExportFormat, assignment: &[i64]) -> Result<Vec<u8>, &'static str> {
+        let literals = assignment
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let solution = format!("SAT\n{} 0\n", literals.join(" "));
+
+        read_solution(&connectivity_index_table(), format, &solution)
+    }
+
+    #[test]
+    fn cnf_round_trip() {
+        let format = ExportFormat::Cnf { min_len: 4 };
+        let (variable_count, hard, soft) = read_dimacs(&export(&format));
+        assert!(soft.is_empty());
+
+        let assignment = solve(variable_count, &hard).expect("a chain of 4 words exists");
+        assert_eq!(import_sat(&format, &assignment), Ok(vec![2, 0, 1, 3]));
+
+        let (variable_count, hard, _) = read_dimacs(&export(&ExportFormat::Cnf { min_len: 5 }));
+        assert_eq!(solve(variable_count, &hard), None);
+    }
+
+    #[test]
+    fn wcnf_round_trip() {
+        let (variable_count, mut hard, soft) = read_dimacs(&export(&ExportFormat::Wcnf));
+        assert_eq!(soft.len(), 4);
+
+        // The optimum satisfies the soft clauses for positions 1 to 3, but not the one for 4
+        hard.extend(soft[..3].iter().cloned());
+
+        let assignment = solve(variable_count, &hard).expect("a chain of 4 words exists");
+        assert_eq!(
+            import_sat(&ExportFormat::Wcnf, &assignment),
+            Ok(vec![2, 0, 1, 3])
+        );
+
+        hard.push(soft[3].clone());
+        assert_eq!(solve(variable_count, &hard), None);
+    }
+
+    #[test]
+    fn sat_solution_formats() {
+        let format = ExportFormat::Wcnf;
+        let table = connectivity_index_table();
+        let chain = |solution: &str| read_solution(&table, &format, solution);
+
+        // Positions are k * 5 + v + 1 for word v at position k
+        assert_eq!(
+            chain("s SATISFIABLE\nc UNSAT cores\nv 3 -1\nv 6 0\n"),
+            Ok(vec![2, 0])
+        );
+        assert_eq!(chain("SAT\n3 6 0\n"), Ok(vec![2, 0]));
+        assert!(chain("s UNSATISFIABLE\n").is_err());
+        assert!(chain("UNSAT\n").is_err());
+        assert!(chain("3 7 0\n").is_err());
+        assert!(chain("3 11 0\n").is_err());
+    }
+
+    /// Finds every assignment of the linear model by brute force. The position variables are only
+    /// tried for assignments of the binaries that satisfy all other constraints.
+    fn feasible_linear_solutions(model: &LinearModel) -> Vec<Vec<(String, i64)>> {
+        let names = model
+            .binaries
+            .iter()
+            .chain(model.bounded.iter().map(|(name, _)| name))
+            .collect::<Vec<_>>();
+
+        let index = |name: &String| names.iter().position(|&n| n == name).unwrap();
+        let binary_count = model.binaries.len();
+
+        let constraints = model
+            .constraints
+            .iter()
+            .map(|c| {
+                let terms = c
+                    .terms
+                    .iter()
+                    .map(|(name, coefficient)| (index(name), *coefficient))
+                    .collect::<Vec<_>>();
+                let binary_only = terms.iter().all(|&(i, _)| i < binary_count);
+
+                (terms, &c.sense, c.rhs, binary_only)
+            })
+            .collect::<Vec<_>>();
+
+        let holds = |values: &[i64], binary_only: bool| {
+            constraints
+                .iter()
+                .filter(|c| c.3 == binary_only)
+                .all(|(terms, sense, rhs, _)| {
+                    let sum = terms.iter().map(|&(i, c)| c * values[i]).sum::<i64>();
+
+                    match sense {
+                        Sense::Equal => sum == *rhs,
+                        Sense::LessOrEqual => sum <= *rhs,
+                    }
+                })
+        };
+
+        let mut solutions = Vec::new();
+        let mut values = vec![0; names.len()];
+
+        for binaries in 0..1u64 << binary_count {
+            for (i, value) in values.iter_mut().enumerate().take(binary_count) {
+                *value = (binaries >> i & 1) as i64;
+            }
+
+            if !holds(&values, true) {
+                continue;
+            }
+
+            let upper = model.bounded[0].1 + 1;
+            let combinations = upper.pow(model.bounded.len() as u32);
+
+            let feasible = (0..combinations).any(|mut combination| {
+                for value in &mut values[binary_count..] {
+                    *value = combination % upper;
+                    combination /= upper;
+                }
+
+                holds(&values, false)
+            });
+
+            if feasible {
+                solutions.push(
+                    names
+                        .iter()
+                        .zip(&values)
+                        .map(|(&name, &value)| (name.clone(), value))
+                        .collect(),
+                );
+            }
+        }
+
+        solutions
+    }
+
+    #[test]
+    fn lp_round_trip() {
+        let table = connectivity_index_table();
+        let model = create_linear_model(&table);
+
+        let lp = export(&ExportFormat::Lp);
+        assert!(model
+            .constraints
+            .iter()
+            .all(|c| lp.contains(&format!(" {}:", c.name))));
+
+        let solutions = feasible_linear_solutions(&model);
+        let mut longest = 0;
+
+        for solution in &solutions {
+            // Written the way CBC does: index, name, value and reduced cost
+            let text = solution
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, value))| *value != 0)
+                .map(|(i, (name, value))| format!("{:>7} {:<16} {:>10} 0\n", i, name, value))
+                .collect::<String>();
+
+            let objective = solution
+                .iter()
+                .filter(|(name, _)| name.starts_with("x_"))
+                .map(|(_, value)| value)
+                .sum::<i64>();
+
+            // Every feasible solution is a valid chain that is as long as its objective
+            let chain = read_solution(&table, &ExportFormat::Lp, &text).unwrap();
+            assert_eq!(chain.len() as i64, objective);
+
+            longest = longest.max(objective);
+        }
+
+        assert_eq!(longest, 4);
+    }
+}
@@ -0,0 +1,604 @@
+use super::connectivity;
+use super::sorting::{self, SortingOrder};
+use super::{chain, packing, sanitize, words};
+use crate::{
+    ChainCover, ChainEnumeration, ChainFilter, ChainInfo, Config, FullChain, PackingMode,
+    SortedSearchConfig, WordGraph,
+};
+use std::collections::{HashMap, HashSet};
+
+impl WordGraph {
+    pub fn new(words: Vec<String>, min_overlap: usize) -> Result<WordGraph, &'static str> {
+        crate::validate_words(&words)?;
+
+        let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+        Ok(WordGraph {
+            min_overlap,
+            words,
+            connectivity_map,
+            known_chain: Vec::new(),
+            known_estimates: HashMap::new(),
+        })
+    }
+
+    /// The words, in the order they were added
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// How many characters are at least required to chain two words together
+    pub fn min_overlap(&self) -> usize {
+        self.min_overlap
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.connectivity_map.contains_key(word)
+    }
+
+    /// The words that can follow `word` in a chain, in the order of `words`. `None` if `word` is
+    /// not in the graph.
+    pub fn followers(&self, word: &str) -> Option<Vec<&str>> {
+        let followers = self.connectivity_map.get(word)?;
+
+        Some(
+            self.words
+                .iter()
+                .filter(|w| followers.contains(*w))
+                .map(String::as_str)
+                .collect(),
+        )
+    }
+
+    /// The words that `word` can follow in a chain, in the order of `words`. `None` if `word` is
+    /// not in the graph.
+    pub fn predecessors(&self, word: &str) -> Option<Vec<&str>> {
+        if !self.contains(word) {
+            return None;
+        }
+
+        Some(
+            self.words
+                .iter()
+                .filter(|w| self.connectivity_map[*w].contains(word))
+                .map(String::as_str)
+                .collect(),
+        )
+    }
+
+    /// How many words can follow `word`
+    pub fn out_degree(&self, word: &str) -> Option<usize> {
+        self.connectivity_map.get(word).map(HashSet::len)
+    }
+
+    /// How many words `word` can follow
+    pub fn in_degree(&self, word: &str) -> Option<usize> {
+        if !self.contains(word) {
+            return None;
+        }
+
+        Some(
+            self.connectivity_map
+                .values()
+                .filter(|followers| followers.contains(word))
+                .count(),
+        )
+    }
+
+    /// How many characters at the end of `left` match the beginning of `right`, ignoring case.
+    /// This may be less than the minimum overlap. `None` if a word is not in the graph.
+    pub fn overlap(&self, left: &str, right: &str) -> Option<usize> {
+        if !self.contains(left) || !self.contains(right) {
+            return None;
+        }
+
+        Some(words::overlapping_chars(left, right))
+    }
+
+    /// Adds a word and links it to all other words. Only words that can now reach the new word in
+    /// a chain forget their bounds, since all other chains were there before.
+    pub fn add_word(&mut self, word: String) -> Result<(), &'static str> {
+        if word.is_empty() {
+            return Err(sanitize::EMPTY_WORD);
+        }
+
+        if self.connectivity_map.contains_key(&word) {
+            return Err("The word is already in the word list.");
+        }
+
+        if self.words.len() >= 256 {
+            return Err("This algorithm is limited to 256 words. Please remove a word first.");
+        }
+
+        let mut followers = HashSet::new();
+
+        for (left, left_followers) in &mut self.connectivity_map {
+            if words::overlapping_chars(left, &word) >= self.min_overlap {
+                left_followers.insert(word.clone());
+            }
+
+            if words::overlapping_chars(&word, left) >= self.min_overlap {
+                followers.insert(left.clone());
+            }
+        }
+
+        self.connectivity_map.insert(word.clone(), followers);
+        self.forget_estimates_reaching(&word);
+        self.words.push(word);
+
+        Ok(())
+    }
+
+    /// Removes a word. Every chain without it was there before, so all bounds still hold. If the
+    /// known chain contained the word, the longer of its two remaining parts is kept.
+    pub fn remove_word(&mut self, word: &str) -> Result<(), &'static str> {
+        if self.connectivity_map.remove(word).is_none() {
+            return Err("The word is not in the word list.");
+        }
+
+        for followers in self.connectivity_map.values_mut() {
+            followers.remove(word);
+        }
+
+        self.words.retain(|w| w != word);
+        self.known_estimates.remove(word);
+
+        if let Some(position) = self.known_chain.iter().position(|w| w == word) {
+            let after = self.known_chain.split_off(position + 1);
+            self.known_chain.pop();
+
+            if after.len() > self.known_chain.len() {
+                self.known_chain = after;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the longest chain like normal mode. Start words whose bound rules out a chain longer
+    /// than the known one aren't searched at all, and the others only look for longer chains.
+    /// Certificates aren't supported, since the bounds of earlier searches can't be checked.
+    pub fn find_longest_chain(
+        &mut self,
+        config: &SortedSearchConfig,
+    ) -> Result<ChainInfo, &'static str> {
+        if config.certificate {
+            return Err("Certificates can't be created for a word graph, since its searches rely on earlier ones.");
+        }
+
+        crate::validate_word_count(&self.words)?;
+
+        let sorted_words = sorting::sort_words(
+            self.words.clone(),
+            &self.connectivity_map,
+            SortingOrder::ForFasterCompletion,
+        );
+
+        let connectivity_index_table =
+            connectivity::create_connectivity_index_table(&sorted_words, &self.connectivity_map);
+
+        let known = chain::KnownBounds {
+            estimates: sorted_words
+                .iter()
+                .map(|word| self.known_estimates.get(word).cloned().unwrap_or(0))
+                .collect(),
+            lower_bound: self.known_chain.len() as u64,
+        };
+
+        let (longest_chain_indices, estimates) = chain::find_longest_chain_parallel(
+            &connectivity_index_table,
+            &sorted_words,
+            &vec![1; sorted_words.len()],
+            config,
+            &chain::ChainConstraints::none(sorted_words.len()),
+            &known,
+        );
+
+        if longest_chain_indices.len() > self.known_chain.len() {
+            self.known_chain = longest_chain_indices
+                .iter()
+                .map(|&w| sorted_words[w as usize].clone())
+                .collect();
+        }
+
+        self.known_estimates = estimates
+            .iter()
+            .map(|&(w, estimate)| (sorted_words[w as usize].clone(), estimate))
+            .collect();
+
+        let indices = (0..self.known_chain.len() as u8).collect::<Vec<_>>();
+
+        Ok(ChainInfo {
+            len: self.known_chain.len() as u8,
+            chain: words::pretty_format_index_chain(&self.known_chain, &indices),
+            score: self.known_chain.len() as u64,
+            certificate: None,
+        })
+    }
+
+    /// Runs a search like `find_longest_chain` in the crate root, but without building the graph
+    /// again. Unlike `WordGraph::find_longest_chain`, it doesn't use or update the bounds of earlier
+    /// searches, so it can be run with any restrictions. The minimum overlap of `config` has to
+    /// match the graph's.
+    pub fn search(&self, config: &Config) -> Result<ChainInfo, &'static str> {
+        if config.min_overlap != self.min_overlap {
+            return Err(
+                "The minimum overlap of the search has to match the one of the word graph.",
+            );
+        }
+
+        if let Some(problem) = crate::validate_input(&self.words, config)
+            .iter()
+            .find(|problem| problem.is_fatal())
+        {
+            return Err(problem.message());
+        }
+
+        crate::search_connectivity_map(self.words.clone(), &self.connectivity_map, config)
+    }
+
+    /// Like `find_chain_cover` in the crate root
+    pub fn find_chain_cover(&self) -> Result<ChainCover, &'static str> {
+        crate::validate_word_count(&self.words)?;
+
+        Ok(crate::find_chain_cover_in_table(
+            &self.words,
+            &self.create_connectivity_index_table(),
+        ))
+    }
+
+    /// Like `find_full_chain` in the crate root
+    pub fn find_full_chain(&self) -> Result<FullChain, &'static str> {
+        crate::validate_word_count(&self.words)?;
+
+        Ok(crate::find_full_chain_in_table(
+            &self.words,
+            &self.create_connectivity_index_table(),
+        ))
+    }
+
+    /// Like `pack_chains` in the crate root. Greedy packing builds a new graph for the remaining
+    /// words after each chain.
+    pub fn pack_chains(
+        &self,
+        count: usize,
+        mode: &PackingMode,
+    ) -> Result<Vec<ChainInfo>, &'static str> {
+        crate::validate_word_count(&self.words)?;
+        crate::validate_packing(&self.words, count, mode)?;
+
+        Ok(match mode {
+            PackingMode::Greedy(ssc) => {
+                packing::pack_greedily(self.words.clone(), self.min_overlap, count, ssc)
+            }
+            PackingMode::Exact => {
+                packing::pack_exactly(&self.words, &self.create_connectivity_index_table(), count)
+            }
+        })
+    }
+
+    /// Like `count_chain_lengths` in the crate root
+    pub fn count_chain_lengths(&self, start: Option<&str>) -> Result<Vec<u64>, &'static str> {
+        crate::validate_word_count(&self.words)?;
+
+        crate::count_chain_lengths_in_table(
+            &self.words,
+            &self.create_connectivity_index_table(),
+            start,
+        )
+    }
+
+    /// Like `enumerate_chains` in the crate root. The enumeration takes a snapshot of the graph,
+    /// so later changes to the graph don't affect it.
+    pub fn enumerate_chains(&self, filter: ChainFilter) -> Result<ChainEnumeration, &'static str> {
+        crate::validate_word_count(&self.words)?;
+
+        Ok(ChainEnumeration::new(
+            self.words.clone(),
+            self.create_connectivity_index_table(),
+            filter,
+        ))
+    }
+
+    /// Word indices follow the order of `words`
+    fn create_connectivity_index_table(&self) -> Vec<Vec<u8>> {
+        connectivity::create_connectivity_index_table(&self.words, &self.connectivity_map)
+    }
+
+    /// Forgets the bounds of all words that have a chain to `word`
+    fn forget_estimates_reaching(&mut self, word: &str) {
+        let mut reaching = HashSet::new();
+        let mut unvisited = vec![word.to_owned()];
+
+        while let Some(right) = unvisited.pop() {
+            for (left, followers) in &self.connectivity_map {
+                if followers.contains(&right) && reaching.insert(left.clone()) {
+                    unvisited.push(left.clone());
+                }
+            }
+        }
+
+        for word in reaching {
+            self.known_estimates.remove(&word);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mode;
+    use rand::rngs::SmallRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    fn random_words(word_count: usize, rng: &mut SmallRng) -> Vec<String> {
+        let mut words = Vec::new();
+
+        while words.len() < word_count {
+            let len = rng.gen_range(2..=4);
+            let word = (0..len)
+                .map(|_| rng.gen_range(b'a'..=b'f') as char)
+                .collect::<String>();
+
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+
+        words
+    }
+
+    fn search_config(pipeline: Option<usize>) -> SortedSearchConfig {
+        SortedSearchConfig {
+            granularity: None,
+            pipeline,
+            verbose: false,
+            certificate: false,
+        }
+    }
+
+    /// The length of the longest chain of a search that starts from scratch
+    fn fresh_search(words: &[String], min_overlap: usize) -> u8 {
+        let search_config = search_config(None);
+
+        let config = Config {
+            min_overlap,
+            metadata: None,
+            pattern: None,
+            objective: crate::Objective::Length,
+            max_chars: None,
+            word_count: None,
+            mode: Mode::Normal(&search_config),
+        };
+
+        crate::find_longest_chain(words.to_vec(), &config)
+            .unwrap()
+            .len
+    }
+
+    fn assert_valid_known_chain(graph: &WordGraph) {
+        let chain = &graph.known_chain;
+
+        assert!(chain.iter().all(|word| graph.contains(word)));
+        assert!(chain.windows(2).all(|pair| {
+            graph
+                .followers(&pair[0])
+                .unwrap()
+                .contains(&pair[1].as_str())
+        }));
+        assert!(chain
+            .iter()
+            .enumerate()
+            .all(|(i, word)| !chain[i + 1..].contains(word)));
+    }
+
+    #[test]
+    fn edits_match_fresh_searches() {
+        let mut rng = SmallRng::seed_from_u64(49);
+
+        for round in 0..6 {
+            let pool = random_words(20, &mut rng);
+            let mut graph = WordGraph::new(pool[..8].to_vec(), 1).unwrap();
+            let config = search_config(if round % 2 == 0 { None } else { Some(3) });
+
+            for _ in 0..30 {
+                let missing = pool
+                    .iter()
+                    .filter(|word| !graph.contains(word))
+                    .collect::<Vec<_>>();
+
+                if graph.words().len() > 1 && (missing.is_empty() || rng.gen_bool(0.4)) {
+                    let word = graph.words().choose(&mut rng).unwrap().clone();
+                    graph.remove_word(&word).unwrap();
+                } else {
+                    let word = (*missing.choose(&mut rng).unwrap()).clone();
+                    graph.add_word(word).unwrap();
+                }
+
+                // Not every edit is followed by a search, so bounds have to survive several edits
+                if rng.gen_bool(0.7) {
+                    let chain_info = graph.find_longest_chain(&config).unwrap();
+
+                    assert_eq!(chain_info.len, fresh_search(graph.words(), 1));
+                    assert_eq!(graph.known_chain.len(), chain_info.len as usize);
+                    assert_valid_known_chain(&graph);
+                }
+
+                let fresh = WordGraph::new(graph.words().to_vec(), 1).unwrap();
+                assert_eq!(graph.connectivity_map, fresh.connectivity_map);
+            }
+        }
+    }
+
+    #[test]
+    fn removing_a_word_keeps_the_longer_part_of_the_chain() {
+        let words = ["ab", "bc", "cd", "de", "ef", "xa"]
+            .iter()
+            .map(|&word| word.to_owned())
+            .collect::<Vec<_>>();
+
+        let mut graph = WordGraph::new(words, 1).unwrap();
+        assert_eq!(
+            graph.find_longest_chain(&search_config(None)).unwrap().len,
+            6
+        );
+
+        graph.remove_word("bc").unwrap();
+        assert_eq!(graph.known_chain, ["cd", "de", "ef"]);
+
+        let chain_info = graph.find_longest_chain(&search_config(None)).unwrap();
+        assert_eq!(chain_info.chain, "cdef");
+    }
+
+    #[test]
+    fn edits_are_checked() {
+        let words = vec!["Lalo".to_owned(), "Oslo".to_owned()];
+        let mut graph = WordGraph::new(words, 1).unwrap();
+
+        assert!(graph.add_word("Lalo".to_owned()).is_err());
+        assert!(graph.add_word(String::new()).is_err());
+        assert!(graph.remove_word("Osaka").is_err());
+
+        graph.remove_word("Lalo").unwrap();
+        graph.remove_word("Oslo").unwrap();
+        assert!(graph.find_longest_chain(&search_config(None)).is_err());
+
+        let certified = SortedSearchConfig {
+            certificate: true,
+            ..search_config(None)
+        };
+
+        graph.add_word("Lalo".to_owned()).unwrap();
+        assert!(graph.find_longest_chain(&certified).is_err());
+        assert_eq!(
+            graph.find_longest_chain(&search_config(None)).unwrap().len,
+            1
+        );
+    }
+
+    #[test]
+    fn queries_match_the_words() {
+        let words = [
+            "Lalo", "Lola", "Oslo", "Loki", "Kilo", "Alaska", "Kansas", "Ask", "Bo",
+        ]
+        .iter()
+        .map(|&word| word.to_owned())
+        .collect::<Vec<_>>();
+
+        let graph = WordGraph::new(words.clone(), 2).unwrap();
+
+        let links = |left: &String, right: &String| {
+            left != right && words::overlapping_chars(left, right) >= 2
+        };
+
+        for word in &words {
+            let followers = words
+                .iter()
+                .filter(|right| links(word, right))
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            let predecessors = words
+                .iter()
+                .filter(|left| links(left, word))
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            assert_eq!(graph.out_degree(word), Some(followers.len()));
+            assert_eq!(graph.in_degree(word), Some(predecessors.len()));
+            assert_eq!(graph.followers(word), Some(followers));
+            assert_eq!(graph.predecessors(word), Some(predecessors));
+
+            for right in &words {
+                assert_eq!(
+                    graph.overlap(word, right),
+                    Some(words::overlapping_chars(word, right))
+                );
+            }
+        }
+
+        assert!(words.iter().any(|word| graph.out_degree(word) > Some(0)));
+        assert_eq!(graph.words(), &words[..]);
+        assert_eq!(graph.min_overlap(), 2);
+        assert!(!graph.contains("Osaka"));
+        assert_eq!(graph.followers("Osaka"), None);
+        assert_eq!(graph.predecessors("Osaka"), None);
+        assert_eq!(graph.in_degree("Osaka"), None);
+        assert_eq!(graph.overlap(&words[0], "Osaka"), None);
+    }
+
+    #[test]
+    fn searches_match_the_crate_root() {
+        let mut rng = SmallRng::seed_from_u64(500);
+        let words = random_words(14, &mut rng);
+        let mut graph = WordGraph::new(words.clone(), 1).unwrap();
+        let search_config = search_config(Some(2));
+
+        for mode in [
+            Mode::Normal(&search_config),
+            Mode::QuickEstimate(&search_config),
+            Mode::Beam { width: 4 },
+        ] {
+            let config = Config {
+                min_overlap: 1,
+                metadata: None,
+                pattern: None,
+                objective: crate::Objective::Length,
+                max_chars: None,
+                word_count: None,
+                mode,
+            };
+
+            let expected = crate::find_longest_chain(words.clone(), &config).unwrap();
+            let found = graph.search(&config).unwrap();
+            assert_eq!(found.len, expected.len);
+            assert_eq!(found.chain, expected.chain);
+
+            let config = Config {
+                min_overlap: 2,
+                ..config
+            };
+
+            assert!(graph.search(&config).is_err());
+        }
+
+        assert_eq!(
+            graph.count_chain_lengths(Some(&words[3])).unwrap(),
+            crate::count_chain_lengths(words.clone(), 1, Some(&words[3])).unwrap()
+        );
+
+        let cover = graph.find_chain_cover().unwrap();
+        let expected_cover = crate::find_chain_cover(words.clone(), 1).unwrap();
+        assert_eq!(cover.chains.len(), expected_cover.chains.len());
+
+        let full = graph.find_full_chain().unwrap();
+        let expected_full = crate::find_full_chain(words.clone(), 1).unwrap();
+        assert_eq!(
+            matches!(full, FullChain::Found(_)),
+            matches!(expected_full, FullChain::Found(_))
+        );
+
+        let filter = ChainFilter {
+            min_len: 3,
+            maximal: true,
+        };
+
+        let enumeration = graph.enumerate_chains(filter).unwrap();
+        let count = enumeration.count();
+        assert_eq!(
+            count,
+            crate::enumerate_chains(words.clone(), 1, filter)
+                .unwrap()
+                .count()
+        );
+
+        // The enumeration keeps the graph it was created from
+        graph.remove_word(&words[0]).unwrap();
+        assert_eq!(enumeration.count(), count);
+        assert_eq!(
+            graph.count_chain_lengths(None).unwrap(),
+            crate::count_chain_lengths(words[1..].to_vec(), 1, None).unwrap()
+        );
+    }
+}
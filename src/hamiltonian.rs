@@ -0,0 +1,428 @@
+use super::connectivity;
+use super::cover;
+use crate::U256;
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How many words are added to a chain between two checks whether another thread is done
+const STOP_CHECK_INTERVAL: u32 = 1024;
+
+/// Looks for a chain that contains every word. Returns the chain, or the reason why there is none.
+pub fn find_full_chain(connectivity_index_table: &[Vec<u8>]) -> Result<Vec<u8>, &'static str> {
+    let predecessor_table = connectivity::create_predecessor_table(connectivity_index_table);
+
+    let starts = check_necessary_conditions(connectivity_index_table, &predecessor_table)?;
+
+    let search = Search {
+        follower_table: connectivity_index_table,
+        follower_masks: connectivity::create_index_masks(connectivity_index_table),
+        all_words: (0..connectivity_index_table.len())
+            .fold(U256::zero(), |acc, w| acc | U256::one() << w),
+        done: AtomicBool::new(false),
+    };
+
+    starts
+        .into_par_iter()
+        .filter_map(|start| search.search_from(start))
+        .find_any(|_| true)
+        .ok_or("There is no chain that contains every word.")
+}
+
+/// Rules out word lists that can't be chained without searching, and returns the words that a
+/// full chain could start with
+fn check_necessary_conditions(
+    follower_table: &[Vec<u8>],
+    predecessor_table: &[Vec<u8>],
+) -> Result<Vec<u8>, &'static str> {
+    let n = follower_table.len();
+
+    if n == 1 {
+        return Ok(vec![0]);
+    }
+
+    let sources = (0..n as u8)
+        .filter(|&w| predecessor_table[w as usize].is_empty())
+        .collect::<Vec<_>>();
+
+    let sinks = (0..n).filter(|&w| follower_table[w].is_empty()).count();
+
+    if sources.len() > 1 {
+        return Err(
+            "More than one word can't follow any other word, but only one can start the chain.",
+        );
+    }
+
+    if sinks > 1 {
+        return Err("More than one word can't be followed by any other word, but only one can end the chain.");
+    }
+
+    if sources.len() == 1 && sinks == 1 && follower_table[sources[0] as usize].is_empty() {
+        return Err("There is a word that can't be linked with any other word.");
+    }
+
+    // Each link of a full chain matches a word with its own follower
+    let links = cover::find_max_matching(follower_table)
+        .iter()
+        .filter(|s| s.is_some())
+        .count();
+
+    if links < n - 1 {
+        return Err("The words can't be linked together often enough to form a single chain.");
+    }
+
+    // Words that can all reach each other form a component. A full chain has to pass through
+    // the components one after another, so each one needs a link to the next.
+    let components = find_components(follower_table);
+
+    let mut component_of = vec![0; n];
+
+    for (index, component) in components.iter().enumerate() {
+        for &word in component {
+            component_of[word as usize] = index;
+        }
+    }
+
+    let linked = components.windows(2).all(|pair| {
+        pair[0].iter().any(|&word| {
+            follower_table[word as usize]
+                .iter()
+                .any(|&f| component_of[f as usize] == component_of[pair[1][0] as usize])
+        })
+    });
+
+    if !linked {
+        return Err("The words fall apart into groups that can't all be passed in a single chain.");
+    }
+
+    if sources.is_empty() {
+        // The chain has to start in the first component
+        Ok(components[0].clone())
+    } else {
+        Ok(sources)
+    }
+}
+
+/// Finds the strongly connected components with Tarjan's algorithm. They are returned in
+/// topological order, so links only lead from a component to later ones.
+fn find_components(follower_table: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    struct Tarjan<'a> {
+        follower_table: &'a [Vec<u8>],
+        index: Vec<Option<usize>>,
+        low_link: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<u8>,
+        next_index: usize,
+        components: Vec<Vec<u8>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, word: u8) {
+            let w = word as usize;
+
+            self.index[w] = Some(self.next_index);
+            self.low_link[w] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(word);
+            self.on_stack[w] = true;
+
+            for &follower in &self.follower_table[w] {
+                let f = follower as usize;
+
+                match self.index[f] {
+                    None => {
+                        self.visit(follower);
+                        self.low_link[w] = self.low_link[w].min(self.low_link[f]);
+                    }
+                    Some(index) if self.on_stack[f] => {
+                        self.low_link[w] = self.low_link[w].min(index);
+                    }
+                    _ => (),
+                }
+            }
+
+            if Some(self.low_link[w]) == self.index[w] {
+                let mut component = Vec::new();
+
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member as usize] = false;
+                    component.push(member);
+
+                    if member == word {
+                        break;
+                    }
+                }
+
+                self.components.push(component);
+            }
+        }
+    }
+
+    let n = follower_table.len();
+
+    let mut tarjan = Tarjan {
+        follower_table,
+        index: vec![None; n],
+        low_link: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+
+    for word in 0..n {
+        if tarjan.index[word].is_none() {
+            tarjan.visit(word as u8);
+        }
+    }
+
+    // Tarjan's algorithm finishes components in reverse topological order
+    tarjan.components.reverse();
+    tarjan.components
+}
+
+struct Search<'a> {
+    follower_table: &'a [Vec<u8>],
+    follower_masks: Vec<U256>,
+    all_words: U256,
+
+    /// Set as soon as any thread finds a full chain
+    done: AtomicBool,
+}
+
+impl<'a> Search<'a> {
+    /// Depth-first search for a full chain starting with `start`. Followers with the fewest ways to
+    /// continue are tried first, and chains that leave words behind which can no longer be
+    /// reached are abandoned early.
+    fn search_from(&self, start: u8) -> Option<Vec<u8>> {
+        let mut chain = vec![start];
+        let mut chain_mask = U256::one() << start;
+        let mut candidates = vec![self.sorted_followers(start, chain_mask)];
+        let mut steps = 0u32;
+
+        while let Some(next) = candidates.last_mut().map(Vec::pop) {
+            if chain_mask == self.all_words {
+                self.done.store(true, Ordering::Relaxed);
+                return Some(chain);
+            }
+
+            steps = steps.wrapping_add(1);
+
+            if steps.is_multiple_of(STOP_CHECK_INTERVAL) && self.done.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            match next {
+                Some(follower) => {
+                    let next_mask = chain_mask | U256::one() << follower;
+
+                    if self.can_be_completed(follower, next_mask) {
+                        chain.push(follower);
+                        chain_mask = next_mask;
+                        candidates.push(self.sorted_followers(follower, chain_mask));
+                    }
+                }
+                None => {
+                    let word = chain.pop().unwrap();
+                    chain_mask = chain_mask & !(U256::one() << word);
+                    candidates.pop();
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Unused followers of `word`, ordered so that the most constrained one is popped first
+    fn sorted_followers(&self, word: u8, chain_mask: U256) -> Vec<u8> {
+        let unused = self.all_words & !chain_mask;
+
+        let mut followers = self.follower_table[word as usize]
+            .iter()
+            .cloned()
+            .filter(|&f| !chain_mask.bit(f as usize))
+            .collect::<Vec<_>>();
+
+        followers.sort_by_key(|&f| Reverse(count_words(self.follower_masks[f as usize] & unused)));
+
+        followers
+    }
+
+    /// Checks that every unused word can still be reached from `last` through unused words, and
+    /// that at most one of them has to end the chain
+    fn can_be_completed(&self, last: u8, chain_mask: U256) -> bool {
+        let unused = self.all_words & !chain_mask;
+
+        let mut reached = U256::zero();
+        let mut frontier = self.follower_masks[last as usize] & unused;
+
+        while !frontier.is_zero() {
+            reached = reached | frontier;
+
+            let mut next = U256::zero();
+
+            while !frontier.is_zero() {
+                let word = frontier.trailing_zeros() as usize;
+                frontier = frontier & !(U256::one() << word);
+                next = next | self.follower_masks[word];
+            }
+
+            frontier = next & unused & !reached;
+        }
+
+        if reached != unused {
+            return false;
+        }
+
+        let ends = (0..self.follower_table.len())
+            .filter(|&w| unused.bit(w) && (self.follower_masks[w] & unused).is_zero())
+            .count();
+
+        ends <= 1
+    }
+}
+
+fn count_words(mask: U256) -> u32 {
+    mask.0.iter().map(|part| part.count_ones()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_table(word_count: usize, link_probability: f64, rng: &mut SmallRng) -> Vec<Vec<u8>> {
+        (0..word_count)
+            .map(|u| {
+                (0..word_count as u8)
+                    .filter(|&v| v as usize != u && rng.gen_bool(link_probability))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Whether any order of the words is a chain
+    fn brute_force_exists(table: &[Vec<u8>]) -> bool {
+        fn extend(table: &[Vec<u8>], chain: &mut Vec<u8>) -> bool {
+            if chain.len() == table.len() {
+                return true;
+            }
+
+            for v in 0..table.len() as u8 {
+                let linked = chain.last().is_none_or(|&u| table[u as usize].contains(&v));
+
+                if linked && !chain.contains(&v) {
+                    chain.push(v);
+
+                    if extend(table, chain) {
+                        return true;
+                    }
+
+                    chain.pop();
+                }
+            }
+
+            false
+        }
+
+        extend(table, &mut Vec::new())
+    }
+
+    fn assert_full_chain(table: &[Vec<u8>], chain: &[u8]) {
+        let mut words = chain.to_vec();
+        words.sort_unstable();
+
+        assert_eq!(words, (0..table.len() as u8).collect::<Vec<_>>());
+        assert!(chain
+            .windows(2)
+            .all(|pair| table[pair[0] as usize].contains(&pair[1])));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(38);
+
+        for _ in 0..300 {
+            let word_count = rng.gen_range(1..=8);
+            let table = random_table(word_count, rng.gen_range(0.1..0.7), &mut rng);
+
+            let found = find_full_chain(&table);
+
+            if let Ok(chain) = &found {
+                assert_full_chain(&table, chain);
+            }
+
+            assert_eq!(found.is_ok(), brute_force_exists(&table));
+        }
+    }
+
+    #[test]
+    fn finds_long_chains() {
+        let mut rng = SmallRng::seed_from_u64(39);
+        let mut table = random_table(60, 0.03, &mut rng);
+
+        // Plant a chain through all words in a random order
+        let mut order = (0..60u8).collect::<Vec<_>>();
+
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.gen_range(0..=i));
+        }
+
+        for pair in order.windows(2) {
+            if !table[pair[0] as usize].contains(&pair[1]) {
+                table[pair[0] as usize].push(pair[1]);
+            }
+        }
+
+        assert_full_chain(&table, &find_full_chain(&table).unwrap());
+    }
+
+    #[test]
+    fn impossible_lists_are_ruled_out() {
+        let impossible = [
+            // 0 and 1 can't follow anything
+            (
+                vec![vec![2], vec![2], vec![]],
+                "More than one word can't follow",
+            ),
+            // 1 and 2 can't be followed by anything
+            (
+                vec![vec![1, 2], vec![], vec![]],
+                "More than one word can't be followed",
+            ),
+            // 2 is isolated
+            (
+                vec![vec![1], vec![0], vec![]],
+                "There is a word that can't be linked",
+            ),
+            // 1, 2 and 3 only link to and from 0, so there are too few links
+            (
+                vec![vec![1, 2, 3], vec![0], vec![0], vec![0]],
+                "The words can't be linked together often enough",
+            ),
+            // {0, 1} and {2, 3} can't reach each other
+            (
+                vec![vec![1], vec![0], vec![3], vec![2]],
+                "The words fall apart into groups",
+            ),
+            // Every condition holds, but from 0 to 3 the chain gets stuck at 0 1 4 2
+            (
+                vec![vec![1, 3], vec![3, 4], vec![1, 4], vec![], vec![2]],
+                "There is no chain",
+            ),
+        ];
+
+        for (table, reason) in &impossible {
+            match find_full_chain(table) {
+                Err(message) => assert!(message.starts_with(reason), "{}", message),
+                Ok(_) => panic!("{:?} has no full chain", table),
+            }
+
+            assert!(!brute_force_exists(table));
+        }
+
+        assert_eq!(find_full_chain(&[vec![]]), Ok(vec![0]));
+    }
+}
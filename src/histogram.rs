@@ -0,0 +1,211 @@
+use super::chain::ChainWalker;
+use super::enumerate;
+use rayon::prelude::*;
+
+/// Up to this many words, chains are counted over subsets of words instead of one by one
+pub const SUBSET_COUNT_LIMIT: usize = 16;
+
+/// Returns how many chains there are with each number of words, optionally only those that start
+/// with `start`. The histogram starts at 0 words and ends at the longest chain.
+pub fn count_chain_lengths(connectivity_index_table: &[Vec<u8>], start: Option<u8>) -> Vec<u64> {
+    let mut histogram = if connectivity_index_table.len() <= SUBSET_COUNT_LIMIT {
+        count_over_subsets(connectivity_index_table, start)
+    } else {
+        count_exhaustively(connectivity_index_table, start)
+    };
+
+    while histogram.last() == Some(&0) {
+        histogram.pop();
+    }
+
+    histogram
+}
+
+/// Counts the chains for every set of words and last word at once, so chains that only differ in
+/// the order of their earlier words are counted together
+fn count_over_subsets(connectivity_index_table: &[Vec<u8>], start: Option<u8>) -> Vec<u64> {
+    let n = connectivity_index_table.len();
+
+    // counts[mask * n + last] is the number of chains that use the words in mask and end with last
+    let mut counts = vec![0u64; n << n];
+
+    for w in 0..n {
+        if start.is_none_or(|start| start as usize == w) {
+            counts[(1 << w) * n + w] = 1;
+        }
+    }
+
+    let mut histogram = vec![0; n + 1];
+
+    // Adding a word only ever makes the mask larger, so every count is complete before it is used
+    for mask in 1..1usize << n {
+        for last in 0..n {
+            let count = counts[mask * n + last];
+
+            if count == 0 {
+                continue;
+            }
+
+            histogram[mask.count_ones() as usize] += count;
+
+            for &follower in &connectivity_index_table[last] {
+                let follower_bit = 1 << follower;
+
+                if mask & follower_bit == 0 {
+                    counts[(mask | follower_bit) * n + follower as usize] += count;
+                }
+            }
+        }
+    }
+
+    histogram
+}
+
+/// Walks every chain, with the chains from each beginning counted in parallel
+fn count_exhaustively(connectivity_index_table: &[Vec<u8>], start: Option<u8>) -> Vec<u64> {
+    let n = connectivity_index_table.len();
+
+    // A single start word needs longer beginnings to split into enough tasks
+    let (start_words, depth) = match start {
+        Some(start) => (vec![start], enumerate::TASK_DEPTH + 2),
+        None => ((0..n).map(|w| w as u8).collect(), enumerate::TASK_DEPTH),
+    };
+
+    let (short_chains, roots) =
+        enumerate::split_chains(connectivity_index_table, &start_words, depth);
+
+    let mut histogram = roots
+        .into_par_iter()
+        .map(|root| {
+            let mut walker = ChainWalker::new(connectivity_index_table, root);
+            let mut histogram = vec![0; n + 1];
+
+            while let Some(chain) = walker.next_chain() {
+                histogram[chain.len()] += 1;
+            }
+
+            histogram
+        })
+        .reduce(
+            || vec![0; n + 1],
+            |mut acc, next| {
+                for (acc, next) in acc.iter_mut().zip(next) {
+                    *acc += next;
+                }
+
+                acc
+            },
+        );
+
+    for chain in short_chains {
+        histogram[chain.len()] += 1;
+    }
+
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChainEnumeration, ChainFilter};
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_table(word_count: usize, link_probability: f64, rng: &mut SmallRng) -> Vec<Vec<u8>> {
+        (0..word_count)
+            .map(|u| {
+                (0..word_count as u8)
+                    .filter(|&v| v as usize != u && rng.gen_bool(link_probability))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Counts the chains with each number of words by enumerating them
+    fn enumerated_counts(table: &[Vec<u8>], start: Option<u8>) -> Vec<u64> {
+        let words = (0..table.len()).map(|w| w.to_string()).collect();
+        let filter = ChainFilter {
+            min_len: 1,
+            maximal: false,
+        };
+
+        let mut histogram = Vec::new();
+
+        for chain in ChainEnumeration::new(words, table.to_vec(), filter).iter() {
+            if start.is_none_or(|start| chain[0] == start.to_string()) {
+                if histogram.len() <= chain.len() {
+                    histogram.resize(chain.len() + 1, 0);
+                }
+
+                histogram[chain.len()] += 1;
+            }
+        }
+
+        histogram
+    }
+
+    fn trimmed(mut histogram: Vec<u64>) -> Vec<u64> {
+        while histogram.last() == Some(&0) {
+            histogram.pop();
+        }
+
+        histogram
+    }
+
+    #[test]
+    fn subsets_and_walks_agree_with_enumeration() {
+        let mut rng = SmallRng::seed_from_u64(48);
+
+        for _ in 0..100 {
+            let word_count = rng.gen_range(1..=9);
+            let table = random_table(word_count, rng.gen_range(0.1..0.6), &mut rng);
+
+            let starts = [None, Some(rng.gen_range(0..word_count) as u8)];
+
+            for &start in &starts {
+                let expected = enumerated_counts(&table, start);
+
+                assert_eq!(trimmed(count_over_subsets(&table, start)), expected);
+                assert_eq!(trimmed(count_exhaustively(&table, start)), expected);
+                assert_eq!(count_chain_lengths(&table, start), expected);
+            }
+
+            let words = (0..word_count).map(|w| w.to_string()).collect();
+            let filter = ChainFilter {
+                min_len: 0,
+                maximal: false,
+            };
+
+            assert_eq!(
+                count_chain_lengths(&table, None).iter().sum::<u64>(),
+                ChainEnumeration::new(words, table, filter).count()
+            );
+        }
+    }
+
+    #[test]
+    fn large_word_lists_are_walked() {
+        let mut rng = SmallRng::seed_from_u64(480);
+        let table = random_table(SUBSET_COUNT_LIMIT + 4, 0.12, &mut rng);
+
+        assert_eq!(
+            count_chain_lengths(&table, None),
+            enumerated_counts(&table, None)
+        );
+        assert_eq!(
+            count_chain_lengths(&table, Some(3)),
+            enumerated_counts(&table, Some(3))
+        );
+    }
+
+    #[test]
+    fn histograms_are_complete() {
+        // 0 -> 1 -> 2, and 3 on its own
+        let table = vec![vec![1], vec![2], vec![], vec![]];
+
+        assert_eq!(count_chain_lengths(&table, None), [0, 4, 2, 1]);
+        assert_eq!(count_chain_lengths(&table, Some(1)), [0, 1, 1]);
+        assert_eq!(count_chain_lengths(&table, Some(3)), [0, 1]);
+        assert_eq!(count_chain_lengths(&[], None), Vec::<u64>::new());
+    }
+}
@@ -0,0 +1,667 @@
+use super::json::{self, Value};
+use crate::{Duplicate, InputConfig, InputFormat, ReadError, WordList, WordLocation, WordMetadata};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// The name that stands for stdin instead of a file
+pub const STDIN_NAME: &str = "-";
+
+/// Separates a word from its options in a text word file
+const OPTION_SEPARATOR: char = '|';
+
+const WEIGHT_ERROR: &str = "The weight must be a whole number that is not negative.";
+
+impl WordList {
+    pub fn new() -> WordList {
+        WordList {
+            words: Vec::new(),
+            metadata: Vec::new(),
+            duplicates: Vec::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Reads one word per line from `reader`. Each word may be followed by a '|' and options:
+    ///
+    /// ```text
+    /// # Comments start with a '#' at the beginning of a line or after whitespace
+    /// Lalo
+    /// Osloos | weight=3  tags=place,city  start required   # Options can be combined
+    /// New York | tags=place
+    /// ```
+    ///
+    /// Lines are trimmed and empty lines are skipped. Everything before the '|' is the word, so
+    /// words can contain spaces. Words that were already read are recorded as duplicates instead
+    /// of being added again, and only their first options count.
+    pub fn read<R: BufRead>(&mut self, source: &str, reader: R) -> Result<(), ReadError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            let location = WordLocation {
+                source: source.to_owned(),
+                line: index + 1,
+            };
+
+            match parse_line(&line) {
+                Ok(Some((word, metadata))) => self.add(word, metadata, location),
+                Ok(None) => (),
+                Err(message) => return Err(ReadError::Parse { location, message }),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads comma- or tab-separated values from `reader`, one word per row. Rows without a word
+    /// are skipped, and so are empty weight and category cells.
+    pub fn read_table<R: Read>(
+        &mut self,
+        source: &str,
+        mut reader: R,
+        delimiter: char,
+        config: &InputConfig,
+    ) -> Result<(), ReadError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        let parse_error = |line, message| ReadError::Parse {
+            location: WordLocation {
+                source: source.to_owned(),
+                line,
+            },
+            message,
+        };
+
+        let mut rows = split_rows(strip_bom(&text), delimiter)
+            .map_err(|(line, message)| parse_error(line, message))?
+            .into_iter();
+
+        let selectors = [
+            Some(config.word_column.unwrap_or("1")),
+            config.weight_column,
+            config.category_column,
+        ];
+
+        let has_header = selectors
+            .iter()
+            .flatten()
+            .any(|selector| selector.parse::<usize>().is_err());
+
+        let header = if has_header { rows.next() } else { None };
+
+        let header_line = header.as_ref().map_or(1, |&(line, _)| line);
+        let header_cells = header.as_ref().map(|(_, cells)| &cells[..]);
+
+        let mut columns = [None; 3];
+
+        for (column, selector) in columns.iter_mut().zip(&selectors) {
+            if let Some(selector) = selector {
+                *column = Some(
+                    find_column(selector, header_cells)
+                        .map_err(|message| parse_error(header_line, message))?,
+                );
+            }
+        }
+
+        for (line, cells) in rows {
+            let cell = |column: Option<usize>| {
+                column
+                    .and_then(|c| cells.get(c))
+                    .map(|cell| cell.trim())
+                    .filter(|cell| !cell.is_empty())
+            };
+
+            let word = match cell(columns[0]) {
+                Some(word) => word,
+                None => continue,
+            };
+
+            let mut metadata = WordMetadata::default();
+
+            if let Some(weight) = cell(columns[1]) {
+                metadata.weight = weight
+                    .parse()
+                    .map_err(|_| parse_error(line, WEIGHT_ERROR))?;
+            }
+
+            if let Some(category) = cell(columns[2]) {
+                metadata.tags.push(category.to_owned());
+            }
+
+            let location = WordLocation {
+                source: source.to_owned(),
+                line,
+            };
+
+            self.add(word, metadata, location);
+        }
+
+        Ok(())
+    }
+
+    /// Reads a JSON array from `reader`. Its elements are either words, or objects that contain a
+    /// word along with an optional weight and category. A category can also be an array of
+    /// categories.
+    ///
+    /// ```text
+    /// ["Lalo", "Osloos"]
+    /// [{"word": "Lalo", "weight": 3, "category": "name"}, {"word": "Osloos"}]
+    /// ```
+    pub fn read_json<R: Read>(
+        &mut self,
+        source: &str,
+        mut reader: R,
+        config: &InputConfig,
+    ) -> Result<(), ReadError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        let parse_error = |line, message| ReadError::Parse {
+            location: WordLocation {
+                source: source.to_owned(),
+                line,
+            },
+            message,
+        };
+
+        let elements = match json::parse(strip_bom(&text)) {
+            Ok(Value::Array(elements)) => elements,
+            Ok(_) => {
+                return Err(parse_error(
+                    1,
+                    "A JSON word file has to contain an array of words or objects.",
+                ))
+            }
+            Err((line, message)) => return Err(parse_error(line, message)),
+        };
+
+        for (line, element) in elements {
+            let (word, metadata) =
+                parse_json_word(&element, config).map_err(|message| parse_error(line, message))?;
+
+            let location = WordLocation {
+                source: source.to_owned(),
+                line,
+            };
+
+            if !word.trim().is_empty() {
+                self.add(word.trim(), metadata, location);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the file at `path`, or stdin if `path` is `-`. The format is taken from `config`, or
+    /// guessed from the extension of the file.
+    pub fn read_path(&mut self, path: &str, config: &InputConfig) -> Result<(), ReadError> {
+        let format = config.format.unwrap_or_else(|| detect_format(path));
+
+        if path == STDIN_NAME {
+            let stdin = io::stdin();
+            let lock = stdin.lock();
+            self.read_format("stdin", lock, format, config)
+        } else {
+            let file = BufReader::new(File::open(path)?);
+            self.read_format(path, file, format, config)
+        }
+    }
+
+    fn read_format<R: BufRead>(
+        &mut self,
+        source: &str,
+        reader: R,
+        format: InputFormat,
+        config: &InputConfig,
+    ) -> Result<(), ReadError> {
+        match format {
+            InputFormat::Text => self.read(source, reader),
+            InputFormat::Csv => self.read_table(source, reader, ',', config),
+            InputFormat::Tsv => self.read_table(source, reader, '\t', config),
+            InputFormat::Json => self.read_json(source, reader, config),
+        }
+    }
+
+    /// Adds `word`, or records it as a duplicate if it was already read
+    fn add(&mut self, word: &str, metadata: WordMetadata, location: WordLocation) {
+        match self.locations.get(word) {
+            Some(first) => self.duplicates.push(Duplicate {
+                word: word.to_owned(),
+                first: first.clone(),
+                duplicate: location,
+            }),
+            None => {
+                self.locations.insert(word.to_owned(), location);
+                self.words.push(word.to_owned());
+                self.metadata.push(metadata);
+            }
+        }
+    }
+}
+
+fn detect_format(path: &str) -> InputFormat {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("csv") => InputFormat::Csv,
+        Some("tsv") | Some("tab") => InputFormat::Tsv,
+        Some("json") => InputFormat::Json,
+        _ => InputFormat::Text,
+    }
+}
+
+/// Spreadsheet programs like to start their exports with a byte order mark
+fn strip_bom(text: &str) -> &str {
+    text.strip_prefix('\u{feff}').unwrap_or(text)
+}
+
+/// The cells of a row, along with the line the row starts on
+type Row = (usize, Vec<String>);
+
+/// Splits comma- or tab-separated values into rows of cells, skipping empty lines. Cells can be
+/// quoted to contain delimiters, line breaks or quotes, which are doubled.
+fn split_rows(text: &str, delimiter: char) -> Result<Vec<Row>, (usize, &'static str)> {
+    let mut rows = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if cell.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => {
+                        if c == '\n' {
+                            line += 1;
+                        }
+
+                        cell.push(c);
+                    }
+                    None => return Err((row_line, "A quoted cell is not closed.")),
+                }
+            },
+            '\n' => {
+                cells.push(std::mem::take(&mut cell));
+
+                if cells.len() > 1 || !cells[0].trim().is_empty() {
+                    rows.push((row_line, std::mem::take(&mut cells)));
+                }
+
+                cells.clear();
+                line += 1;
+                row_line = line;
+            }
+            '\r' if chars.peek() == Some(&'\n') => (),
+            c if c == delimiter => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+
+    cells.push(cell);
+
+    if cells.len() > 1 || !cells[0].trim().is_empty() {
+        rows.push((row_line, cells));
+    }
+
+    Ok(rows)
+}
+
+/// Finds a column by its position starting at 1, or by its name in the header row
+fn find_column(selector: &str, header: Option<&[String]>) -> Result<usize, &'static str> {
+    match selector.parse::<usize>() {
+        Ok(0) => Err("Column positions start at 1."),
+        Ok(position) => Ok(position - 1),
+        Err(_) => header
+            .and_then(|cells| cells.iter().position(|cell| cell.trim() == selector))
+            .ok_or("A column that was given by name is missing in the header row."),
+    }
+}
+
+/// Takes the word and its metadata out of an element of a JSON word file
+fn parse_json_word<'a>(
+    element: &'a Value,
+    config: &InputConfig,
+) -> Result<(&'a str, WordMetadata), &'static str> {
+    let mut metadata = WordMetadata::default();
+
+    let word = match element {
+        Value::String(word) => return Ok((word, metadata)),
+        Value::Object(_) => element.get(config.word_column.unwrap_or("word")),
+        _ => return Err("The elements of a JSON word file have to be words or objects."),
+    };
+
+    let word = match word {
+        Some(Value::String(word)) => word,
+        _ => {
+            return Err("Every object in a JSON word file needs a word, which has to be a string.")
+        }
+    };
+
+    match element.get(config.weight_column.unwrap_or("weight")) {
+        Some(Value::Number(weight)) => {
+            metadata.weight = weight.parse().map_err(|_| WEIGHT_ERROR)?
+        }
+        Some(Value::Null) | None => (),
+        Some(_) => return Err(WEIGHT_ERROR),
+    }
+
+    const CATEGORY_ERROR: &str = "A category has to be a string or an array of strings.";
+
+    match element.get(config.category_column.unwrap_or("category")) {
+        Some(Value::String(category)) => metadata.tags.push(category.clone()),
+        Some(Value::Array(categories)) => {
+            for (_, category) in categories {
+                match category {
+                    Value::String(category) => metadata.tags.push(category.clone()),
+                    _ => return Err(CATEGORY_ERROR),
+                }
+            }
+        }
+        Some(Value::Null) | None => (),
+        Some(_) => return Err(CATEGORY_ERROR),
+    }
+
+    Ok((word, metadata))
+}
+
+/// Returns the word on a line along with its options, or `None` if there is no word on it
+fn parse_line(line: &str) -> Result<Option<(&str, WordMetadata)>, &'static str> {
+    let line = strip_comment(line);
+
+    let (word, options) = match line.find(OPTION_SEPARATOR) {
+        Some(index) => (line[..index].trim(), &line[index + 1..]),
+        None => (line.trim(), ""),
+    };
+
+    if word.is_empty() {
+        return if options.trim().is_empty() {
+            Ok(None)
+        } else {
+            Err("Options need a word in front of them.")
+        };
+    }
+
+    let mut metadata = WordMetadata::default();
+
+    for token in options.split_whitespace() {
+        if let Some(weight) = token.strip_prefix("weight=") {
+            metadata.weight = weight.parse().map_err(|_| WEIGHT_ERROR)?;
+        } else if let Some(tags) = token.strip_prefix("tags=") {
+            if tags.split(',').any(str::is_empty) {
+                return Err("Tags must be separated by single commas.");
+            }
+
+            metadata.tags.extend(tags.split(',').map(ToOwned::to_owned));
+        } else {
+            match token {
+                "start" => metadata.start = true,
+                "end" => metadata.end = true,
+                "required" => metadata.required = true,
+                _ => return Err("Unknown word option. Options are weight=<n>, tags=<a,b>, start, end and required."),
+            }
+        }
+    }
+
+    Ok(Some((word, metadata)))
+}
+
+/// Cuts off a comment, which starts with a '#' at the beginning of the line or after whitespace.
+/// This keeps words like "C#" intact.
+fn strip_comment(line: &str) -> &str {
+    let mut previous = None;
+
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..index];
+        }
+
+        previous = Some(c);
+    }
+
+    line
+}
+
+impl Default for WordList {
+    fn default() -> WordList {
+        WordList::new()
+    }
+}
+
+impl Default for WordMetadata {
+    fn default() -> WordMetadata {
+        WordMetadata {
+            weight: 1,
+            tags: Vec::new(),
+            start: false,
+            end: false,
+            required: false,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> ReadError {
+        ReadError::Io(error)
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(error) => write!(f, "{}", error),
+            ReadError::Parse { location, message } => write!(f, "{}: {}", location, message),
+        }
+    }
+}
+
+impl fmt::Display for WordLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
+    }
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} is a duplicate of {}",
+            self.word, self.duplicate, self.first
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_text(text: &str) -> Result<WordList, ReadError> {
+        let mut word_list = WordList::new();
+        word_list.read("test", text.as_bytes())?;
+        Ok(word_list)
+    }
+
+    fn read_valid_text(text: &str) -> WordList {
+        read_text(text).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn error_line(result: Result<WordList, ReadError>) -> usize {
+        match result {
+            Err(ReadError::Parse { location, .. }) => location.line,
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn text_keeps_words_with_spaces() {
+        let word_list = read_valid_text("New York\n  Kansas City  \nSan Jose | tags=city start\n");
+
+        assert_eq!(word_list.words, ["New York", "Kansas City", "San Jose"]);
+        assert_eq!(word_list.metadata[2].tags, ["city"]);
+        assert!(word_list.metadata[2].start);
+        assert!(!word_list.metadata[0].start);
+    }
+
+    #[test]
+    fn text_reads_options_and_comments() {
+        let text =
+            "# a comment\n\nLalo | weight=3 tags=name,short end required # why\nC#\nOslo#1\n";
+        let word_list = read_valid_text(text);
+
+        assert_eq!(word_list.words, ["Lalo", "C#", "Oslo#1"]);
+
+        let metadata = &word_list.metadata[0];
+        assert_eq!(metadata.weight, 3);
+        assert_eq!(metadata.tags, ["name", "short"]);
+        assert!(!metadata.start && metadata.end && metadata.required);
+        assert_eq!(word_list.metadata[1].weight, 1);
+    }
+
+    #[test]
+    fn text_records_duplicates() {
+        let word_list = read_valid_text("Lalo\nOslo\nLalo | weight=5\n");
+
+        assert_eq!(word_list.words, ["Lalo", "Oslo"]);
+        assert_eq!(word_list.metadata[0].weight, 1);
+        assert_eq!(word_list.duplicates.len(), 1);
+        assert_eq!(word_list.duplicates[0].first.line, 1);
+        assert_eq!(word_list.duplicates[0].duplicate.line, 3);
+    }
+
+    #[test]
+    fn text_errors_name_the_line() {
+        assert_eq!(error_line(read_text("Lalo\n\nOslo | weight=-1\n")), 3);
+        assert_eq!(error_line(read_text("Lalo | tags=a,,b\n")), 1);
+        assert_eq!(error_line(read_text("Lalo\nOslo | fast\n")), 2);
+        assert_eq!(error_line(read_text("Lalo\n | start\n")), 2);
+    }
+
+    fn config<'a>(
+        word_column: Option<&'a str>,
+        weight_column: Option<&'a str>,
+        category_column: Option<&'a str>,
+    ) -> InputConfig<'a> {
+        InputConfig {
+            format: None,
+            word_column,
+            weight_column,
+            category_column,
+        }
+    }
+
+    fn read_table(
+        text: &str,
+        delimiter: char,
+        config: &InputConfig,
+    ) -> Result<WordList, ReadError> {
+        let mut word_list = WordList::new();
+        word_list.read_table("test", text.as_bytes(), delimiter, config)?;
+        Ok(word_list)
+    }
+
+    fn read_json(text: &str, config: &InputConfig) -> Result<WordList, ReadError> {
+        let mut word_list = WordList::new();
+        word_list.read_json("test", text.as_bytes(), config)?;
+        Ok(word_list)
+    }
+
+    #[test]
+    fn csv_reads_columns_by_position() {
+        let text = "Lalo,3,name\n\n\"New York, NY\",,place\n,5,\nOslo\n";
+        let word_list = read_table(text, ',', &config(None, Some("2"), Some("3")))
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(word_list.words, ["Lalo", "New York, NY", "Oslo"]);
+        assert_eq!(word_list.metadata[0].weight, 3);
+        assert_eq!(word_list.metadata[0].tags, ["name"]);
+        assert_eq!(word_list.metadata[1].weight, 1);
+        assert_eq!(word_list.metadata[1].tags, ["place"]);
+        assert!(word_list.metadata[2].tags.is_empty());
+    }
+
+    #[test]
+    fn tsv_reads_columns_by_header() {
+        let text = "\u{feff}weight\tword\n2\t\"Say \"\"hi\"\"\"\n1\tLalo\r\n";
+        let word_list = read_table(text, '\t', &config(Some("word"), Some("weight"), None))
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(word_list.words, ["Say \"hi\"", "Lalo"]);
+        assert_eq!(word_list.metadata[0].weight, 2);
+    }
+
+    #[test]
+    fn table_errors_name_the_line() {
+        let by_position = config(None, Some("2"), None);
+        let by_name = config(Some("word"), None, None);
+
+        assert_eq!(
+            error_line(read_table(
+                "Lalo,1\n\"Oslo\nos\",2\nAla,x\n",
+                ',',
+                &by_position
+            )),
+            4
+        );
+        assert_eq!(
+            error_line(read_table("Lalo,1\nOslo,\"2\n", ',', &by_position)),
+            2
+        );
+        assert_eq!(error_line(read_table("\nname\nLalo\n", ',', &by_name)), 2);
+        assert_eq!(
+            error_line(read_table("Lalo\n", ',', &config(Some("0"), None, None))),
+            1
+        );
+    }
+
+    #[test]
+    fn json_reads_words_and_objects() {
+        let text =
+            r#"["Lalo", {"word": "Oslo", "weight": 2, "category": ["a", "b"]}, {"word": " "}]"#;
+        let word_list =
+            read_json(text, &InputConfig::default()).unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(word_list.words, ["Lalo", "Oslo"]);
+        assert_eq!(word_list.metadata[1].weight, 2);
+        assert_eq!(word_list.metadata[1].tags, ["a", "b"]);
+
+        let text = r#"[{"w": "Lalo", "points": 4, "kind": "name"}]"#;
+        let word_list = read_json(text, &config(Some("w"), Some("points"), Some("kind")))
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(word_list.words, ["Lalo"]);
+        assert_eq!(word_list.metadata[0].weight, 4);
+        assert_eq!(word_list.metadata[0].tags, ["name"]);
+    }
+
+    #[test]
+    fn json_errors_name_the_line() {
+        let default = InputConfig::default();
+
+        assert_eq!(
+            error_line(read_json("[\n\"Lalo\",\n{\"weight\": 1}\n]", &default)),
+            3
+        );
+        assert_eq!(
+            error_line(read_json(
+                "[\n\"Lalo\",\n{\"word\": \"Oslo\", \"weight\": -1}]",
+                &default
+            )),
+            3
+        );
+        assert_eq!(error_line(read_json("[\"Lalo\",\n\n7]", &default)), 3);
+        assert_eq!(error_line(read_json("{\"word\": \"Lalo\"}", &default)), 1);
+        assert_eq!(error_line(read_json("[\"Lalo\"\n\"Oslo\"]", &default)), 2);
+    }
+}
@@ -0,0 +1,426 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// How deeply arrays and objects may be nested. Word files only need a few levels, and the limit
+/// keeps deeply nested input from overflowing the stack of the recursive parser.
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value. Numbers are kept as they were written, so they can be parsed into whatever type
+/// is needed.
+pub enum Value {
+    Null,
+
+    /// Word files have no use for the value of a boolean
+    Bool,
+
+    /// The number as it was written
+    Number(String),
+
+    String(String),
+
+    /// The elements, each with the line it starts on
+    Array(Vec<(usize, Value)>),
+
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Looks up `key` in an object. Returns `None` for anything else than an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a JSON document. Errors come with the line they occurred on.
+pub fn parse(text: &str) -> Result<Value, (usize, &'static str)> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        line: 1,
+        depth: 0,
+    };
+
+    let value = parser.parse_value()?;
+
+    parser.skip_whitespace();
+
+    if parser.chars.peek().is_some() {
+        return Err(parser.error("There is more text after the JSON value."));
+    }
+
+    Ok(value)
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+
+    /// How many arrays and objects enclose the current value
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_value(&mut self) -> Result<Value, (usize, &'static str)> {
+        self.skip_whitespace();
+
+        match self.chars.peek() {
+            Some('[') => self.parse_nested(Parser::parse_array),
+            Some('{') => self.parse_nested(Parser::parse_object),
+            Some('"') => self.parse_string().map(Value::String),
+            Some('t') => self.parse_literal("true", Value::Bool),
+            Some('f') => self.parse_literal("false", Value::Bool),
+            Some('n') => self.parse_literal("null", Value::Null),
+            Some(&c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("Expected a JSON value.")),
+            None => Err(self.error("The JSON ends too early.")),
+        }
+    }
+
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value, (usize, &'static str)>,
+    ) -> Result<Value, (usize, &'static str)> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("The JSON is nested too deeply."));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn parse_array(&mut self) -> Result<Value, (usize, &'static str)> {
+        self.chars.next();
+
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.chars.peek() == Some(&']') {
+            self.chars.next();
+            return Ok(Value::Array(elements));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let line = self.line;
+            elements.push((line, self.parse_value()?));
+
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Value::Array(elements)),
+                _ => return Err(self.error("Expected ',' or ']' in an array.")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, (usize, &'static str)> {
+        self.chars.next();
+
+        let mut members = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.chars.peek() == Some(&'}') {
+            self.chars.next();
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            if self.chars.peek() != Some(&'"') {
+                return Err(self.error("Expected a string as key in an object."));
+            }
+
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+
+            if self.chars.next() != Some(':') {
+                return Err(self.error("Expected ':' after a key in an object."));
+            }
+
+            members.push((key, self.parse_value()?));
+
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Value::Object(members)),
+                _ => return Err(self.error("Expected ',' or '}' in an object.")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, (usize, &'static str)> {
+        self.chars.next();
+
+        let mut string = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let c = match self.chars.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("Unknown escape sequence in a string.")),
+                    };
+
+                    string.push(c);
+                }
+                Some('\n') | None => return Err(self.error("A string is not closed.")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    /// Parses the part of a `\u` escape after the `u`, including a second escape if the first one
+    /// is only half of a surrogate pair
+    fn parse_unicode_escape(&mut self) -> Result<char, (usize, &'static str)> {
+        const INVALID: &str = "Invalid unicode escape sequence in a string.";
+
+        let high = self.parse_hex()?;
+
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                return Err(self.error(INVALID));
+            }
+
+            let low = self.parse_hex()?;
+
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error(INVALID));
+            }
+
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+
+        std::char::from_u32(code).ok_or_else(|| self.error(INVALID))
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, (usize, &'static str)> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("Invalid unicode escape sequence in a string."))?;
+
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Value, (usize, &'static str)> {
+        let mut number = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+
+            number.push(c);
+            self.chars.next();
+        }
+
+        if !is_number(&number) {
+            return Err(self.error("Invalid number."));
+        }
+
+        Ok(Value::Number(number))
+    }
+
+    fn parse_literal(
+        &mut self,
+        literal: &str,
+        value: Value,
+    ) -> Result<Value, (usize, &'static str)> {
+        for expected in literal.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(self.error("Expected a JSON value."));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '\n' => self.line += 1,
+                ' ' | '\t' | '\r' => (),
+                _ => return,
+            }
+
+            self.chars.next();
+        }
+    }
+
+    fn error(&self, message: &'static str) -> (usize, &'static str) {
+        (self.line, message)
+    }
+}
+
+/// Checks the JSON number grammar, which is stricter than Rust's: no leading zeros, no leading
+/// '+', and digits on both sides of the decimal point
+fn is_number(number: &str) -> bool {
+    let mut bytes = number.as_bytes();
+
+    let skip_digits = |bytes: &mut &[u8]| {
+        let count = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+        *bytes = &bytes[count..];
+        count
+    };
+
+    if let Some(rest) = bytes.strip_prefix(b"-") {
+        bytes = rest;
+    }
+
+    match bytes.first() {
+        Some(b'0') => bytes = &bytes[1..],
+        Some(b'1'..=b'9') => {
+            skip_digits(&mut bytes);
+        }
+        _ => return false,
+    }
+
+    if let Some(rest) = bytes.strip_prefix(b".") {
+        bytes = rest;
+
+        if skip_digits(&mut bytes) == 0 {
+            return false;
+        }
+    }
+
+    if let Some(rest) = bytes
+        .strip_prefix(b"e")
+        .or_else(|| bytes.strip_prefix(b"E"))
+    {
+        bytes = rest;
+
+        if let Some(rest) = bytes
+            .strip_prefix(b"+")
+            .or_else(|| bytes.strip_prefix(b"-"))
+        {
+            bytes = rest;
+        }
+
+        if skip_digits(&mut bytes) == 0 {
+            return false;
+        }
+    }
+
+    bytes.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_string(json: &str) -> String {
+        match parse(json) {
+            Ok(Value::String(string)) => string,
+            _ => panic!("expected a string"),
+        }
+    }
+
+    fn error_line(json: &str) -> usize {
+        match parse(json) {
+            Err((line, _)) => line,
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn strings_with_escapes() {
+        assert_eq!(
+            parse_string(r#""a\"\\\/\b\f\n\r\t""#),
+            "a\"\\/\u{8}\u{c}\n\r\t"
+        );
+        assert_eq!(parse_string(r#""\u00e9\u00C9""#), "éÉ");
+        assert_eq!(parse_string(r#""\ud83d\ude00""#), "\u{1f600}");
+
+        assert!(parse(r#""\ud83d""#).is_err());
+        assert!(parse(r#""\ud83d\u0041""#).is_err());
+        assert!(parse(r#""\ude00""#).is_err());
+        assert!(parse(r#""\u12g4""#).is_err());
+        assert!(parse(r#""\x""#).is_err());
+    }
+
+    #[test]
+    fn numbers() {
+        for number in &["0", "-0", "12", "-3.25", "1e5", "1E+5", "2.5e-3"] {
+            match parse(number) {
+                Ok(Value::Number(parsed)) => assert_eq!(&parsed, number),
+                _ => panic!("{} should be a number", number),
+            }
+        }
+
+        for number in &["01", "1.", ".5", "+1", "-", "1e", "1.2.3", "--1", "1e+"] {
+            assert!(parse(number).is_err(), "{} should not be a number", number);
+        }
+    }
+
+    #[test]
+    fn arrays_and_objects() {
+        let value = parse("[\n  {\"word\": \"Lalo\", \"weight\": 3},\n  \"Oslo\", null, true\n]")
+            .unwrap_or_else(|_| panic!("expected valid JSON"));
+
+        let elements = match value {
+            Value::Array(elements) => elements,
+            _ => panic!("expected an array"),
+        };
+
+        assert_eq!(
+            elements.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            [2, 3, 3, 3]
+        );
+
+        match elements[0].1.get("weight") {
+            Some(Value::Number(weight)) => assert_eq!(weight, "3"),
+            _ => panic!("expected a weight"),
+        }
+
+        assert!(elements[0].1.get("category").is_none());
+        assert!(elements[1].1.get("word").is_none());
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(error_line("[\n\"Lalo\",\n\"Oslo\"\n\"Alala\"]"), 4);
+        assert_eq!(error_line("[\n{\"word\" \"Lalo\"}]"), 2);
+        assert_eq!(error_line("[\"Lalo\",\n\n\"Oslo]"), 3);
+        assert_eq!(error_line("[1, 2]\n\nx"), 3);
+        assert_eq!(error_line("[\"Lalo\",\n"), 2);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&"[".repeat(1_000_000)).is_err());
+    }
+}
@@ -3,37 +3,323 @@ extern crate rand;
 extern crate rayon;
 extern crate uint;
 
+mod annealing;
+mod beam;
+mod certificate;
 mod chain;
+mod check;
 mod connectivity;
+mod cover;
+mod decompose;
+mod enumerate;
+mod export;
+mod graph;
+mod hamiltonian;
+mod histogram;
+mod input;
+mod json;
+mod local_search;
+mod packing;
+mod pattern;
 mod random_chain;
+mod sanitize;
 mod sorting;
 mod tasks;
 mod words;
 
 use sorting::SortingOrder;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::time::Duration;
 
-use uint::construct_uint;
+use u256::U256;
 
-construct_uint! {
-    struct U256(4);
+#[allow(clippy::manual_div_ceil, clippy::assign_op_pattern)]
+mod u256 {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+/// Words read from one or more files. Every word is only kept once, and all further occurrences
+/// are recorded as duplicates.
+pub struct WordList {
+    /// The words, in the order they were first read
+    pub words: Vec<String>,
+
+    /// The options of each word in `words`
+    pub metadata: Vec<WordMetadata>,
+
+    pub duplicates: Vec<Duplicate>,
+
+    locations: HashMap<String, WordLocation>,
+}
+
+#[derive(Clone)]
+pub struct WordLocation {
+    /// The file name, or `stdin`
+    pub source: String,
+
+    /// Line numbers start at 1
+    pub line: usize,
+}
+
+pub struct Duplicate {
+    pub word: String,
+    pub first: WordLocation,
+    pub duplicate: WordLocation,
+}
+
+/// Where a word can be found in the input
+#[derive(Clone)]
+pub enum WordPosition {
+    /// The index of the word in a list of words
+    Index(usize),
+
+    /// Where the word was read from a word file
+    Location(WordLocation),
+}
+
+/// A word that is most likely a mistake in the word list
+pub struct WordProblem {
+    pub word: String,
+    pub position: WordPosition,
+    pub kind: WordProblemKind,
+}
+
+pub enum WordProblemKind {
+    /// Empty words can't be chained with anything
+    Empty,
+
+    /// The word isn't longer than the minimum overlap, so it can't be chained with any other word
+    TooShort,
+
+    /// The word already appeared at `first`
+    Duplicate { first: WordPosition },
+
+    /// The word only differs in case from `other` at `first`. Overlaps ignore case, so the two
+    /// chain together perfectly, which is rarely what was intended.
+    CaseDuplicate { other: String, first: WordPosition },
+}
+
+/// What is done about words with problems
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputPolicy {
+    /// Any problem is an error
+    Error,
+
+    /// Problems are only reported. Duplicates and empty words are removed anyway, since they can't
+    /// be searched.
+    Warn,
+
+    /// Like `Warn`, but words that only differ in case from an earlier word are removed as well
+    Dedupe,
+
+    /// Like `Dedupe`, but words that are too short to be chained are removed as well
+    Drop,
+}
+
+/// Anything wrong with the input of a search
+pub enum InputProblem {
+    /// The search can't be started like this
+    Invalid(&'static str),
+
+    Word(WordProblem),
+}
+
+/// Options that can be given for each word in a word file
+#[derive(Clone)]
+pub struct WordMetadata {
+    /// How much the word is worth. Defaults to 1.
+    pub weight: u32,
+
+    pub tags: Vec<String>,
+
+    /// If any word is marked with `start`, chains may only start with marked words
+    pub start: bool,
+
+    /// If any word is marked with `end`, chains may only end with marked words
+    pub end: bool,
+
+    /// Chains have to contain every word marked with `required`
+    pub required: bool,
+}
+
+pub enum ReadError {
+    Io(io::Error),
+
+    /// The word file doesn't follow its format
+    Parse {
+        location: WordLocation,
+        message: &'static str,
+    },
+}
+
+/// A pattern over the tags of the words that a chain has to match, like a regular expression over
+/// words. See `TagPattern::parse`.
+pub struct TagPattern {
+    nodes: Vec<pattern::Node>,
+    start: usize,
+}
+
+/// How the words are stored in a word file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputFormat {
+    /// One word per line, followed by options. See `WordList::read`.
+    Text,
+
+    /// Comma-separated values
+    Csv,
+
+    /// Tab-separated values
+    Tsv,
+
+    /// An array of words, or an array of objects that contain a word each
+    Json,
+}
+
+/// Tells where words and their metadata are found in CSV, TSV and JSON input
+#[derive(Default)]
+pub struct InputConfig<'a> {
+    /// If not set, the format is guessed from the file extension. Files without a known extension
+    /// and stdin are read as text.
+    pub format: Option<InputFormat>,
+
+    /// The column of the words. CSV and TSV columns are either a header name, or a position
+    /// starting at 1. A file is expected to have a header row if any column is given by name.
+    /// For JSON objects, this is the key of the word. Defaults to the first column, or the key
+    /// `word`.
+    pub word_column: Option<&'a str>,
+
+    /// The column of the word weights. For JSON objects, this defaults to the key `weight`.
+    pub weight_column: Option<&'a str>,
+
+    /// The column of the word categories, which are added to the tags of a word. For JSON
+    /// objects, this defaults to the key `category`.
+    pub category_column: Option<&'a str>,
 }
 
 pub struct Config<'a> {
     /// How many characters are at least required to chain two words together
     pub min_overlap: usize,
 
+    /// The options of each word, in the same order as the words. Only normal and quick estimation
+    /// mode support restrictions on how chains start and end, or which words they contain.
+    pub metadata: Option<&'a [WordMetadata]>,
+
+    /// A pattern over the tags of the words that every chain has to match. Only normal and quick
+    /// estimation mode support it.
+    pub pattern: Option<&'a TagPattern>,
+
+    /// What makes one chain better than another
+    pub objective: Objective,
+
+    /// The most characters the glued chain may have, for chains that have to fit somewhere. Only
+    /// normal and quick estimation mode support it.
+    pub max_chars: Option<usize>,
+
+    /// The exact number of words of the chain. Only normal and quick estimation mode support it.
+    pub word_count: Option<usize>,
+
     /// Mode of search
     pub mode: Mode<'a>,
 }
 
+/// What a search maximizes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+    /// The number of words in the chain
+    Length,
+
+    /// The sum of the weights of the words in the chain, where more words break ties. Only normal
+    /// and quick estimation mode support it.
+    Weight,
+}
+
 pub struct SortedSearchConfig {
     /// How many levels of recursion the task generation algorithm uses
     /// Lower values decrease management and memory overhead, but can lead to load imbalance
     /// Generally, larger workloads run faster with higher values.
     pub granularity: Option<u8>,
 
+    /// How many start words are searched concurrently. `None` searches them one after another.
+    /// Later start words may not be able to use the estimates of earlier ones that are still in
+    /// flight, but the result is the same either way.
+    pub pipeline: Option<usize>,
+
     /// Enables more detailed intermediate output
     pub verbose: bool,
+
+    /// Attaches a certificate to the result that proves that no longer chain exists
+    pub certificate: bool,
+}
+
+#[derive(Clone)]
+pub struct RandomSearchConfig {
+    /// Seed for the random number generators. Each thread derives its generator from the seed
+    /// and its thread index. If `None`, the generators are seeded from system entropy.
+    pub seed: Option<u64>,
+
+    /// Runs the search on the calling thread only. Combined with a seed, this makes runs
+    /// reproducible bit for bit (on the same platform).
+    pub single_threaded: bool,
+
+    /// How strongly each new chain moves the average chain lengths that guide the search.
+    /// Must be in (0, 1]; higher values learn faster, but also chase noise.
+    pub learning_rate: f32,
+
+    /// Words are picked with a likelihood proportional to their average chain length raised to
+    /// this power. 1 is plain proportional selection, 0 ignores all evidence, and higher values
+    /// concentrate on the best known words.
+    pub selection_exponent: f32,
+
+    /// Probability in [0, 1] of picking a word uniformly at random instead of by its average
+    pub exploration_rate: f64,
+
+    /// Every this many chains, all averages decay towards their initial value, so that stale
+    /// evidence is forgotten over time. `None` disables decay.
+    pub decay_interval: Option<u64>,
+
+    /// Fraction in [0, 1] by which averages decay at each interval. 1 resets them completely.
+    pub decay: f32,
+
+    /// Every this many chains, each thread merges its learned averages with those of the other
+    /// threads. `None` lets every thread learn on its own. Must be `None` if `single_threaded` is
+    /// set.
+    pub share_interval: Option<u64>,
+}
+
+impl Default for RandomSearchConfig {
+    fn default() -> Self {
+        RandomSearchConfig {
+            seed: None,
+            single_threaded: false,
+            learning_rate: 0.05,
+            selection_exponent: 1.0,
+            exploration_rate: 0.0,
+            decay_interval: None,
+            decay: 1.0,
+            share_interval: None,
+        }
+    }
+}
+
+pub struct AnnealingConfig {
+    /// Seed for the random number generator. If `None`, it is seeded from system entropy.
+    pub seed: Option<u64>,
+
+    /// Temperature at the start of the search. Higher temperatures make it more likely that
+    /// a move which shortens the chain is accepted.
+    pub initial_temperature: f64,
+
+    /// Temperature at the end of the search. The temperature decreases exponentially from the
+    /// initial to the final temperature over the course of the time limit.
+    pub final_temperature: f64,
+
+    /// How long the search runs before the longest chain found is returned
+    pub time_limit: Duration,
 }
 
 pub enum Mode<'a> {
@@ -45,80 +331,769 @@ pub enum Mode<'a> {
 
     /// Uses a heuristic search to guess the longest chain. Will never terminate and there is
     /// no guarantee that you will get the correct result. Useful for long word lists.
-    RandomSearch,
+    RandomSearch(&'a RandomSearchConfig),
+
+    /// Uses simulated annealing to guess the longest chain. Terminates after the configured time
+    /// limit, but there is no guarantee that you will get the correct result.
+    Annealing(&'a AnnealingConfig),
+
+    /// Grows chains level by level from all start words, keeping only the `width` most promising
+    /// chains at each level. Deterministic and bounded in memory, but there is no guarantee that
+    /// you will get the correct result.
+    Beam { width: usize },
+}
+
+pub enum ExportFormat {
+    /// Integer linear program in CPLEX LP format
+    Lp,
+
+    /// Integer linear program in free MPS format
+    Mps,
+
+    /// Satisfiability problem in DIMACS CNF format, which is satisfiable if and only if there is a
+    /// chain of at least `min_len` words
+    Cnf { min_len: usize },
+
+    /// Weighted partial MaxSAT problem in DIMACS WCNF format
+    Wcnf,
 }
 
 pub struct ChainInfo {
     pub len: u8,
     pub chain: String,
+
+    /// The value of the chain for the objective of the search: its length, or the sum of the
+    /// weights of its words
+    pub score: u64,
+
+    /// Proof of optimality, if one was requested
+    pub certificate: Option<Certificate>,
+}
+
+/// Evidence that there is no chain longer than `chain`. Lists an upper bound on the longest chain
+/// starting with each word, in an order where each bound can be checked using only the bounds
+/// before it. Can be checked with `Certificate::verify`, which is much faster than a new search.
+pub struct Certificate {
+    pub min_overlap: usize,
+
+    /// All words, in the order of their bounds
+    pub words: Vec<String>,
+
+    /// The bound for each word in `words`
+    pub bounds: Vec<u8>,
+
+    /// The longest chain, as indices into `words`
+    pub chain: Vec<u8>,
+}
+
+/// A chain to be checked by `verify_chain`
+pub enum ChainInput<'a> {
+    /// The words of the chain, one after another
+    Words(&'a [String]),
+
+    /// The chain as rendered by a search, with the overlapping characters of neighbouring words
+    /// merged
+    Glued(&'a str),
+}
+
+pub struct ChainReport {
+    /// The words of the chain. For glued chains, this is the part that could be split into words.
+    pub words: Vec<String>,
+
+    /// Everything that is wrong with the chain, in the order it appears. Empty for a valid chain.
+    pub problems: Vec<ChainProblem>,
+}
+
+/// Positions count the words of the chain, starting at 0
+pub enum ChainProblem {
+    /// The word isn't in the word list
+    MissingWord { position: usize, word: String },
+
+    /// The word was already used earlier in the chain
+    RepeatedWord {
+        position: usize,
+        first_position: usize,
+        word: String,
+    },
+
+    /// The words at `position` and `position + 1` overlap by fewer than `min_overlap` characters
+    BadJunction {
+        position: usize,
+        left: String,
+        right: String,
+        overlap: usize,
+    },
+
+    /// The glued chain can't be split into words of the word list from this character on
+    Unsplittable { offset: usize },
+}
+
+/// Chains that together use every word exactly once
+pub struct ChainCover {
+    /// The chains, longest first
+    pub chains: Vec<ChainInfo>,
+
+    /// Whether there are no chains that use every word with fewer chains. Always true for up to
+    /// 20 words, since those are covered exactly.
+    pub optimal: bool,
+}
+
+pub enum PackingMode<'a> {
+    /// Finds the longest chain, then the longest chain among the remaining words, and so on
+    Greedy(&'a SortedSearchConfig),
+
+    /// Finds the chains that use the most words together. Limited to 20 words.
+    Exact,
+}
+
+pub enum FullChain {
+    /// A chain that contains every word
+    Found(ChainInfo),
+
+    /// There is no chain that contains every word, for this reason
+    Impossible(&'static str),
+}
+
+/// One way to split a glued chain into words
+pub struct Decomposition {
+    pub words: Vec<String>,
+
+    /// How many characters each word shares with the next one
+    pub overlaps: Vec<usize>,
+}
+
+/// A word list along with which words can follow each other, built once and reused for any number
+/// of queries and searches. Words can be added and removed one at a time, and
+/// `WordGraph::find_longest_chain` reuses what the searches before it found out, as far as it is
+/// still true. When only a few words changed, this is much faster than a new search.
+pub struct WordGraph {
+    min_overlap: usize,
+    words: Vec<String>,
+    connectivity_map: connectivity::ConnectivityMap,
+
+    /// A chain that is known to exist, usually the result of the last search. New searches only
+    /// look for longer chains.
+    known_chain: Vec<String>,
+
+    /// Upper bounds on the longest chain starting with each word, for all words where the last
+    /// search's bound still holds
+    known_estimates: HashMap<String, u64>,
+}
+
+/// Which chains `enumerate_chains` finds
+#[derive(Clone, Copy, Default)]
+pub struct ChainFilter {
+    /// Only chains with at least this many words
+    pub min_len: usize,
+
+    /// Only chains that can't be extended at either end, because every word that could come
+    /// before or after them is already part of them
+    pub maximal: bool,
+}
+
+/// All chains of a word list that pass a filter. They can be walked one after another with
+/// `iter`, in parallel with `par_for_each`, or just counted with `count`.
+pub struct ChainEnumeration {
+    words: Vec<String>,
+    connectivity_index_table: Vec<Vec<u8>>,
+    follower_masks: Vec<U256>,
+    predecessor_masks: Vec<U256>,
+    filter: ChainFilter,
+}
+
+/// Walks the chains of a `ChainEnumeration` lazily, one start word after another. Each chain is
+/// returned as its words.
+pub struct Chains<'a> {
+    enumeration: &'a ChainEnumeration,
+    next_start: usize,
+    walker: Option<chain::ChainWalker<'a>>,
+}
+
+/// Reads the words from all files at `paths`, where `-` stands for stdin
+pub fn read_word_files(paths: &[&str], config: &InputConfig) -> Result<WordList, ReadError> {
+    let mut word_list = WordList::new();
+
+    for path in paths {
+        word_list.read_path(path, config)?;
+    }
+
+    Ok(word_list)
 }
 
 pub fn find_longest_chain(words: Vec<String>, config: &Config) -> Result<ChainInfo, &'static str> {
-    validate_input(&words, &config)?;
+    if let Some(problem) = validate_input(&words, config)
+        .iter()
+        .find(|problem| problem.is_fatal())
+    {
+        return Err(problem.message());
+    }
 
     let connectivity_map = connectivity::create_connectivity_map(&words, config.min_overlap);
 
+    search_connectivity_map(words, &connectivity_map, config)
+}
+
+fn search_connectivity_map(
+    words: Vec<String>,
+    connectivity_map: &connectivity::ConnectivityMap,
+    config: &Config,
+) -> Result<ChainInfo, &'static str> {
     match config.mode {
         Mode::Normal(ssc) => start_sorted_search(
             words,
-            &connectivity_map,
+            connectivity_map,
+            config,
             ssc,
             SortingOrder::ForFasterCompletion,
         ),
 
         Mode::QuickEstimate(ssc) => start_sorted_search(
             words,
-            &connectivity_map,
+            connectivity_map,
+            config,
             ssc,
             SortingOrder::ForFasterIntermediateResults,
         ),
 
-        Mode::RandomSearch => start_random_search(words, &connectivity_map),
+        Mode::RandomSearch(rsc) => start_random_search(words, connectivity_map, rsc),
+
+        Mode::Annealing(ac) => start_annealing_search(words, connectivity_map, ac),
+
+        Mode::Beam { width } => start_beam_search(words, connectivity_map, width),
+    }
+}
+
+/// Writes the search for the longest chain as a model for an external solver. Words keep the
+/// position they have in `words`, so `import_solution` must be called with the same list.
+pub fn export_model<W: io::Write>(
+    words: Vec<String>,
+    min_overlap: usize,
+    format: &ExportFormat,
+    writer: &mut W,
+) -> io::Result<()> {
+    validate_words(&words).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    export::write_model(&connectivity_index_table, format, writer)
+}
+
+/// Reads the solution of an external solver for a model written by `export_model`
+pub fn import_solution(
+    words: Vec<String>,
+    min_overlap: usize,
+    format: &ExportFormat,
+    solution: &str,
+) -> Result<ChainInfo, &'static str> {
+    validate_words(&words)?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    let chain_indices = export::read_solution(&connectivity_index_table, format, solution)?;
+
+    Ok(ChainInfo {
+        len: chain_indices.len() as u8,
+        chain: words::pretty_format_index_chain(&words, &chain_indices),
+        score: chain_indices.len() as u64,
+        certificate: None,
+    })
+}
+
+/// Checks a chain that was found elsewhere against a word list, and reports everything that
+/// keeps it from being a valid chain
+pub fn verify_chain(
+    words: &[String],
+    min_overlap: usize,
+    chain: &ChainInput,
+) -> Result<ChainReport, &'static str> {
+    if words.is_empty() {
+        return Err("There are no words to chain. Please add some words to your file.");
+    }
+
+    match chain {
+        ChainInput::Words([]) | ChainInput::Glued("") => Err("The chain is empty."),
+        _ => Ok(check::check_chain(words, min_overlap, chain)),
+    }
+}
+
+/// Splits all words into as few chains as possible. If that is a single chain, all words can be
+/// chained together. Exact for up to 20 words, heuristic for larger word lists.
+pub fn find_chain_cover(words: Vec<String>, min_overlap: usize) -> Result<ChainCover, &'static str> {
+    validate_words(&words)?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    Ok(find_chain_cover_in_table(&words, &connectivity_index_table))
+}
+
+fn find_chain_cover_in_table(words: &[String], connectivity_index_table: &[Vec<u8>]) -> ChainCover {
+    let (cover, optimal) = cover::find_min_cover(connectivity_index_table);
+
+    ChainCover {
+        chains: cover.iter().map(|chain| create_chain_info(words, chain)).collect(),
+        optimal,
+    }
+}
+
+/// Finds up to `count` chains that don't share any words, so that they use as many words as
+/// possible together
+pub fn pack_chains(
+    words: Vec<String>,
+    min_overlap: usize,
+    count: usize,
+    mode: &PackingMode,
+) -> Result<Vec<ChainInfo>, &'static str> {
+    validate_words(&words)?;
+    validate_packing(&words, count, mode)?;
+
+    match mode {
+        PackingMode::Greedy(ssc) => Ok(packing::pack_greedily(words, min_overlap, count, ssc)),
+
+        PackingMode::Exact => {
+            let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+            let connectivity_index_table =
+                connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+            Ok(packing::pack_exactly(&words, &connectivity_index_table, count))
+        }
+    }
+}
+
+/// Checks whether all words fit into a single chain. Cheap checks rule out most word lists that
+/// don't without a search, and the search stops as soon as it finds a chain.
+pub fn find_full_chain(words: Vec<String>, min_overlap: usize) -> Result<FullChain, &'static str> {
+    validate_words(&words)?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    Ok(find_full_chain_in_table(&words, &connectivity_index_table))
+}
+
+fn find_full_chain_in_table(words: &[String], connectivity_index_table: &[Vec<u8>]) -> FullChain {
+    match hamiltonian::find_full_chain(connectivity_index_table) {
+        Ok(chain) => FullChain::Found(create_chain_info(words, &chain)),
+        Err(reason) => FullChain::Impossible(reason),
+    }
+}
+
+/// Prepares to walk all chains that pass `filter`. Nothing is searched until the chains are
+/// requested, and then only as far as needed. There are a lot of chains in all but the smallest
+/// word lists, so use a filter.
+pub fn enumerate_chains(
+    words: Vec<String>,
+    min_overlap: usize,
+    filter: ChainFilter,
+) -> Result<ChainEnumeration, &'static str> {
+    validate_words(&words)?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    Ok(ChainEnumeration::new(words, connectivity_index_table, filter))
+}
+
+/// Counts the chains of each length, optionally only those that start with `start`. Entry `i` of
+/// the result is the number of chains with `i` words, up to the longest chain. Word lists of up to
+/// 16 words are counted over subsets of words, which is fast. Larger ones have to walk every chain,
+/// which takes about as long as a search without estimates.
+pub fn count_chain_lengths(
+    words: Vec<String>,
+    min_overlap: usize,
+    start: Option<&str>,
+) -> Result<Vec<u64>, &'static str> {
+    validate_words(&words)?;
+
+    let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+
+    count_chain_lengths_in_table(&words, &connectivity_index_table, start)
+}
+
+fn count_chain_lengths_in_table(
+    words: &[String],
+    connectivity_index_table: &[Vec<u8>],
+    start: Option<&str>,
+) -> Result<Vec<u64>, &'static str> {
+    let start = match start {
+        Some(start) => Some(
+            words
+                .iter()
+                .position(|word| word == start)
+                .ok_or("The start word is not in the word list.")? as u8,
+        ),
+        None => None,
+    };
+
+    Ok(histogram::count_chain_lengths(
+        connectivity_index_table,
+        start,
+    ))
+}
+
+/// Builds the result for a chain of indices into `words`, which is as long as it scores
+fn create_chain_info(words: &[String], chain: &[u8]) -> ChainInfo {
+    ChainInfo {
+        len: chain.len() as u8,
+        chain: words::pretty_format_index_chain(words, chain),
+        score: chain.len() as u64,
+        certificate: None,
     }
 }
 
+/// Splits a chain that was rendered by a search back into its words. Returns every way to do so,
+/// so more than one result means that the chain is ambiguous. Case is ignored, just like it is for
+/// overlaps.
+pub fn decompose_chain(
+    words: &[String],
+    min_overlap: usize,
+    glued: &str,
+) -> Result<Vec<Decomposition>, &'static str> {
+    if words.is_empty() {
+        return Err("There are no words to chain. Please add some words to your file.");
+    }
+
+    if glued.is_empty() {
+        return Err("The chain is empty.");
+    }
+
+    Ok(decompose::decompose(words, min_overlap, glued))
+}
+
 fn start_sorted_search(
     words: Vec<String>,
     connectivity_map: &connectivity::ConnectivityMap,
+    config: &Config,
     sorted_search_config: &SortedSearchConfig,
     sorting_order: SortingOrder,
 ) -> Result<ChainInfo, &'static str> {
-    let words = sorting::sort_words(words, &connectivity_map, sorting_order);
+    // Sorting reorders the words, so their metadata has to be looked up by word afterwards
+    let metadata_by_word = config.metadata.map(|metadata| {
+        words
+            .iter()
+            .cloned()
+            .zip(metadata.iter())
+            .collect::<HashMap<_, _>>()
+    });
+
+    let words = sorting::sort_words(words, connectivity_map, sorting_order);
+
+    let default_metadata = WordMetadata::default();
+
+    let sorted_metadata = words
+        .iter()
+        .map(|word| match &metadata_by_word {
+            Some(metadata_by_word) => metadata_by_word[word],
+            None => &default_metadata,
+        })
+        .collect::<Vec<_>>();
+
+    let automaton = match config.pattern {
+        Some(pattern) => Some(pattern::TagAutomaton::new(
+            pattern,
+            &sorted_metadata
+                .iter()
+                .map(|metadata| &metadata.tags[..])
+                .collect::<Vec<_>>(),
+        )?),
+        None => None,
+    };
+
+    let constraints = chain::ChainConstraints {
+        max_chars: config.max_chars,
+        word_count: config.word_count,
+        ..chain::ChainConstraints::new(&sorted_metadata, automaton)
+    };
+
+    let weights = sorted_metadata
+        .iter()
+        .map(|metadata| match config.objective {
+            Objective::Length => 1,
+            Objective::Weight => metadata.weight as u64,
+        })
+        .collect::<Vec<_>>();
 
     let connectivity_index_table =
-        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+        connectivity::create_connectivity_index_table(&words, connectivity_map);
 
-    let longest_chain_indices = chain::find_longest_chain_parallel(
+    let (longest_chain_indices, estimates) = chain::find_longest_chain_parallel(
         &connectivity_index_table,
         &words,
-        sorted_search_config.granularity,
-        sorted_search_config.verbose,
+        &weights,
+        sorted_search_config,
+        &constraints,
+        &chain::KnownBounds::default(),
     );
 
+    if longest_chain_indices.is_empty() {
+        return Err("No chain satisfies the restrictions of the word file, the tag pattern and the length bounds.");
+    }
+
+    let certificate = if sorted_search_config.certificate {
+        // Certificates are only allowed for the longest chain, so every estimate is a length
+        let estimates = estimates
+            .iter()
+            .map(|&(start, estimate)| (start, estimate as u8))
+            .collect::<Vec<_>>();
+
+        Some(Certificate::new(
+            &words,
+            config.min_overlap,
+            &longest_chain_indices,
+            &estimates,
+        ))
+    } else {
+        None
+    };
+
     Ok(ChainInfo {
         len: longest_chain_indices.len() as u8,
         chain: words::pretty_format_index_chain(&words, &longest_chain_indices),
+        score: longest_chain_indices
+            .iter()
+            .map(|&w| weights[w as usize])
+            .sum(),
+        certificate,
     })
 }
 
 fn start_random_search(
     words: Vec<String>,
     connectivity_map: &connectivity::ConnectivityMap,
+    random_search_config: &RandomSearchConfig,
 ) -> Result<ChainInfo, &'static str> {
     let connectivity_index_table =
-        connectivity::create_connectivity_index_table(&words, &connectivity_map);
+        connectivity::create_connectivity_index_table(&words, connectivity_map);
 
-    random_chain::find_longest(connectivity_index_table, words);
+    random_chain::find_longest(connectivity_index_table, words, random_search_config);
 
     unreachable!();
 }
 
-fn validate_input(words: &Vec<String>, _config: &Config) -> Result<(), &'static str> {
+fn start_annealing_search(
+    words: Vec<String>,
+    connectivity_map: &connectivity::ConnectivityMap,
+    annealing_config: &AnnealingConfig,
+) -> Result<ChainInfo, &'static str> {
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, connectivity_map);
+
+    let longest_chain_indices =
+        annealing::find_longest(&connectivity_index_table, &words, annealing_config);
+
+    Ok(ChainInfo {
+        len: longest_chain_indices.len() as u8,
+        chain: words::pretty_format_index_chain(&words, &longest_chain_indices),
+        score: longest_chain_indices.len() as u64,
+        certificate: None,
+    })
+}
+
+fn start_beam_search(
+    words: Vec<String>,
+    connectivity_map: &connectivity::ConnectivityMap,
+    width: usize,
+) -> Result<ChainInfo, &'static str> {
+    let connectivity_index_table =
+        connectivity::create_connectivity_index_table(&words, connectivity_map);
+
+    let longest_chain_indices = beam::find_longest(&connectivity_index_table, &words, width);
+
+    Ok(ChainInfo {
+        len: longest_chain_indices.len() as u8,
+        chain: words::pretty_format_index_chain(&words, &longest_chain_indices),
+        score: longest_chain_indices.len() as u64,
+        certificate: None,
+    })
+}
+
+/// Checks everything a search needs, and returns all problems that were found. Only fatal problems
+/// stop `find_longest_chain`.
+pub fn validate_input(words: &[String], config: &Config) -> Vec<InputProblem> {
+    let mut problems = Vec::new();
+
+    if let Err(message) = validate_word_count(words) {
+        problems.push(InputProblem::Invalid(message));
+    }
+
+    if let Err(message) = validate_restrictions(words, config) {
+        problems.push(InputProblem::Invalid(message));
+    }
+
+    let mode = match config.mode {
+        Mode::RandomSearch(rsc) => validate_random_search_config(rsc),
+        Mode::Annealing(ac) => validate_annealing_config(ac),
+        Mode::Beam { width: 0 } => Err("The beam width must be at least 1."),
+        _ => Ok(()),
+    };
+
+    if let Err(message) = mode {
+        problems.push(InputProblem::Invalid(message));
+    }
+
+    problems.extend(
+        find_word_problems(words, config.min_overlap)
+            .into_iter()
+            .map(InputProblem::Word),
+    );
+
+    problems
+}
+
+/// Finds empty, duplicate and too short words, as well as words that only differ in case
+pub fn find_word_problems(words: &[String], min_overlap: usize) -> Vec<WordProblem> {
+    sanitize::find_word_problems(words, min_overlap, WordPosition::Index)
+        .into_iter()
+        .map(|(_, problem)| problem)
+        .collect()
+}
+
+/// Checks the metadata, and that only modes which support restrictions on chains or the weight
+/// objective get them
+fn validate_restrictions(words: &[String], config: &Config) -> Result<(), &'static str> {
+    if config.metadata.is_some_and(|metadata| metadata.len() != words.len()) {
+        return Err("There has to be exactly one metadata entry for each word.");
+    }
+
+    if config.objective == Objective::Weight {
+        match config.mode {
+            Mode::Normal(ssc) | Mode::QuickEstimate(ssc) if ssc.certificate => {
+                return Err("Certificates can only be created for the longest chain, not for the one with the highest weight.")
+            }
+            Mode::Normal(_) | Mode::QuickEstimate(_) => (),
+            _ => return Err("Only normal and quick estimation mode can maximize the weight of chains."),
+        }
+    }
+
+    if config.word_count == Some(0) {
+        return Err("A chain has to consist of at least one word.");
+    }
+
+    let restricted = config.pattern.is_some()
+        || config.max_chars.is_some()
+        || config.word_count.is_some()
+        || config
+            .metadata
+            .is_some_and(|metadata| metadata.iter().any(|m| m.start || m.end || m.required));
+
+    match config.mode {
+        Mode::Normal(ssc) | Mode::QuickEstimate(ssc) if restricted && ssc.certificate => Err(
+            "Certificates can't be created when the word file, a tag pattern or length bounds restrict which chains are allowed.",
+        ),
+        Mode::Normal(_) | Mode::QuickEstimate(_) => Ok(()),
+        _ if restricted => Err(
+            "Only normal and quick estimation mode support start, end and required words, tag patterns and length bounds.",
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn validate_packing(words: &[String], count: usize, mode: &PackingMode) -> Result<(), &'static str> {
+    if count == 0 {
+        return Err("The number of chains must be at least 1.");
+    }
+
+    match mode {
+        PackingMode::Exact if words.len() > cover::EXACT_COVER_LIMIT => Err(
+            "Exact packing is limited to 20 words. Please use greedy packing for longer lists.",
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Makes sure that the words can be searched at all
+fn validate_words(words: &[String]) -> Result<(), &'static str> {
+    validate_word_count(words)?;
+
+    if words.iter().any(String::is_empty) {
+        return Err(sanitize::EMPTY_WORD);
+    }
+
+    let mut seen = HashSet::with_capacity(words.len());
+
+    if !words.iter().all(|word| seen.insert(word)) {
+        return Err(sanitize::DUPLICATE_WORD);
+    }
+
+    Ok(())
+}
+
+fn validate_word_count(words: &[String]) -> Result<(), &'static str> {
     if words.len() > 256 {
         return Err(
             "This algorithm is limited to 256 words. Please remove some words from your file.",
         );
     };
 
+    if words.is_empty() {
+        return Err("There are no words to chain. Please add some words to your file.");
+    }
+
+    Ok(())
+}
+
+fn validate_annealing_config(config: &AnnealingConfig) -> Result<(), &'static str> {
+    if !(config.final_temperature > 0.0 && config.final_temperature.is_finite()) {
+        return Err("The final temperature must be a finite, positive number.");
+    }
+
+    if !(config.initial_temperature >= config.final_temperature
+        && config.initial_temperature.is_finite())
+    {
+        return Err("The initial temperature must be finite and at least the final temperature.");
+    }
+
+    if config.time_limit == Duration::from_secs(0) {
+        return Err("The time limit must be larger than 0.");
+    }
+
+    Ok(())
+}
+
+fn validate_random_search_config(config: &RandomSearchConfig) -> Result<(), &'static str> {
+    if !(config.learning_rate > 0.0 && config.learning_rate <= 1.0) {
+        return Err("The learning rate must be larger than 0 and at most 1.");
+    }
+
+    if !(config.selection_exponent >= 0.0 && config.selection_exponent.is_finite()) {
+        return Err("The selection exponent must be a finite, non-negative number.");
+    }
+
+    if !(0.0..=1.0).contains(&config.exploration_rate) {
+        return Err("The exploration rate must be between 0 and 1.");
+    }
+
+    if config.decay_interval == Some(0) {
+        return Err("The decay interval must be at least 1.");
+    }
+
+    if !(0.0..=1.0).contains(&config.decay) {
+        return Err("The decay must be between 0 and 1.");
+    }
+
+    if config.share_interval == Some(0) {
+        return Err("The share interval must be at least 1.");
+    }
+
+    if config.single_threaded && config.share_interval.is_some() {
+        return Err("A single thread has no other threads to share its averages with.");
+    }
+
     Ok(())
 }
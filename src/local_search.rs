@@ -0,0 +1,170 @@
+use super::chain::create_chain_mask;
+use super::connectivity;
+use crate::U256;
+
+/// How many links of a chain a subpath replacement spans at most
+const MAX_REPLACED_LINKS: usize = 4;
+
+/// How many links a replacement subpath may be longer than the one it replaces
+const MAX_ADDED_LINKS: usize = 2;
+
+/// Improves existing chains by local modifications. Built once from a connectivity index table
+/// and reusable for any number of chains.
+pub struct LocalSearch {
+    follower_table: Vec<Vec<u8>>,
+    predecessor_table: Vec<Vec<u8>>,
+    follower_masks: Vec<U256>,
+    predecessor_masks: Vec<U256>,
+}
+
+impl LocalSearch {
+    pub fn new(connectivity_index_table: &[Vec<u8>]) -> LocalSearch {
+        let predecessor_table = connectivity::create_predecessor_table(connectivity_index_table);
+
+        LocalSearch {
+            follower_masks: connectivity::create_index_masks(connectivity_index_table),
+            predecessor_masks: connectivity::create_index_masks(&predecessor_table),
+            follower_table: connectivity_index_table.to_vec(),
+            predecessor_table,
+        }
+    }
+
+    /// Applies improving moves until none of them can make `chain` any longer. Returns whether
+    /// the chain was changed.
+    pub fn improve_chain(&self, chain: &mut Vec<u8>) -> bool {
+        debug_assert!(!chain.is_empty());
+
+        let initial_len = chain.len();
+
+        // Moves are tried from cheapest to most expensive, starting over after each success
+        while self.extend_ends(chain) || self.insert_words(chain) || self.replace_subpaths(chain) {}
+
+        chain.len() > initial_len
+    }
+
+    /// Appends unused words at the end and prepends them at the front
+    fn extend_ends(&self, chain: &mut Vec<u8>) -> bool {
+        let mut chain_mask = create_chain_mask(chain);
+        let mut extended = false;
+
+        while let Some(&follower) = self.follower_table[*chain.last().unwrap() as usize]
+            .iter()
+            .find(|&&f| !chain_mask.bit(f as usize))
+        {
+            chain.push(follower);
+            chain_mask = chain_mask | U256::one() << follower;
+            extended = true;
+        }
+
+        while let Some(&predecessor) = self.predecessor_table[chain[0] as usize]
+            .iter()
+            .find(|&&p| !chain_mask.bit(p as usize))
+        {
+            chain.insert(0, predecessor);
+            chain_mask = chain_mask | U256::one() << predecessor;
+            extended = true;
+        }
+
+        extended
+    }
+
+    /// Inserts unused words between two neighbours that both link to them
+    fn insert_words(&self, chain: &mut Vec<u8>) -> bool {
+        let mut chain_mask = create_chain_mask(chain);
+        let mut inserted = false;
+        let mut i = 0;
+
+        while i + 1 < chain.len() {
+            let candidates = self.follower_masks[chain[i] as usize]
+                & self.predecessor_masks[chain[i + 1] as usize]
+                & !chain_mask;
+
+            if candidates.is_zero() {
+                i += 1;
+            } else {
+                let word = candidates.trailing_zeros() as u8;
+
+                // Don't advance, the new word might allow another insertion after it
+                chain.insert(i + 1, word);
+                chain_mask = chain_mask | U256::one() << word;
+                inserted = true;
+            }
+        }
+
+        inserted
+    }
+
+    /// Replaces short subpaths with longer ones between the same two words, using words that
+    /// are either unused or part of the replaced subpath
+    fn replace_subpaths(&self, chain: &mut Vec<u8>) -> bool {
+        let chain_mask = create_chain_mask(chain);
+
+        for links in 1..=MAX_REPLACED_LINKS {
+            for start in 0..chain.len().saturating_sub(links) {
+                let end = start + links;
+
+                let inner_mask = create_chain_mask(&chain[start + 1..end]);
+                let blocked_mask = chain_mask & !inner_mask;
+
+                let mut path = vec![chain[start]];
+
+                if self.find_longer_path(
+                    &mut path,
+                    chain[end],
+                    links + 1,
+                    links + MAX_ADDED_LINKS,
+                    blocked_mask,
+                ) {
+                    chain.splice(start..=end, path);
+
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Depth-limited search for a path from the last word of `path` to `target` with between
+    /// `min_links` and `max_links` links that avoids all words in `blocked_mask`
+    fn find_longer_path(
+        &self,
+        path: &mut Vec<u8>,
+        target: u8,
+        min_links: usize,
+        max_links: usize,
+        blocked_mask: U256,
+    ) -> bool {
+        let links = path.len() - 1;
+
+        if links == max_links {
+            return false;
+        }
+
+        let last = *path.last().unwrap() as usize;
+
+        if links + 1 >= min_links && self.follower_masks[last].bit(target as usize) {
+            path.push(target);
+
+            return true;
+        }
+
+        let path_mask = create_chain_mask(path) | blocked_mask | U256::one() << target;
+
+        for &follower in &self.follower_table[last] {
+            if path_mask.bit(follower as usize) {
+                continue;
+            }
+
+            path.push(follower);
+
+            if self.find_longer_path(path, target, min_links, max_links, blocked_mask) {
+                return true;
+            }
+
+            path.pop();
+        }
+
+        false
+    }
+}
@@ -5,48 +5,151 @@ extern crate wordchain;
 use clap::{App, Arg};
 use std::fs::File;
 use std::io;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::str;
-use std::time::Instant;
-use wordchain::{Config, SortedSearchConfig};
+use std::time::{Duration, Instant};
+use wordchain::{
+    AnnealingConfig, Certificate, ChainInfo, ChainInput, Config, ExportFormat, FullChain,
+    InputConfig, InputFormat, InputPolicy, Objective, PackingMode, RandomSearchConfig, SortedSearchConfig,
+    TagPattern, WordList, WordMetadata,
+};
 
 arg_enum! {
     #[allow(non_camel_case_types)]
     enum Mode {
         normal,
         quickestimate,
-        random
+        random,
+        annealing,
+        beam,
+        cover,
+        fullchain,
+        pack,
+        count
+    }
+}
+
+arg_enum! {
+    #[allow(non_camel_case_types)]
+    enum WordFileFormat {
+        text,
+        csv,
+        tsv,
+        json
+    }
+}
+
+arg_enum! {
+    #[allow(non_camel_case_types)]
+    enum Policy {
+        error,
+        warn,
+        dedupe,
+        drop
+    }
+}
+
+arg_enum! {
+    #[allow(non_camel_case_types)]
+    enum ModelFormat {
+        lp,
+        mps,
+        cnf,
+        wcnf
     }
 }
 
 const ARG_MIN_OVERLAP: &str = "min-overlap";
 const ARG_WORD_FILE: &str = "word-file";
+const ARG_INPUT_FORMAT: &str = "input-format";
+const ARG_COLUMN: &str = "column";
+const ARG_WEIGHT_COLUMN: &str = "weight-column";
+const ARG_CATEGORY_COLUMN: &str = "category-column";
+const ARG_INPUT_POLICY: &str = "input-policy";
 const ARG_MODE: &str = "mode";
 const ARG_GRANULARITY: &str = "granularity";
 const ARG_VERBOSE: &str = "verbose";
+const ARG_PIPELINE: &str = "pipeline";
+const ARG_SEED: &str = "seed";
+const ARG_SINGLE_THREADED: &str = "single-threaded";
+const ARG_LEARNING_RATE: &str = "learning-rate";
+const ARG_SELECTION_EXPONENT: &str = "selection-exponent";
+const ARG_EXPLORATION_RATE: &str = "exploration-rate";
+const ARG_DECAY_INTERVAL: &str = "decay-interval";
+const ARG_DECAY: &str = "decay";
+const ARG_SHARE_INTERVAL: &str = "share-interval";
+const ARG_TEMPERATURE: &str = "temperature";
+const ARG_FINAL_TEMPERATURE: &str = "final-temperature";
+const ARG_TIME_LIMIT: &str = "time-limit";
+const ARG_BEAM_WIDTH: &str = "beam-width";
+const ARG_MODEL_FORMAT: &str = "format";
+const ARG_MIN_LEN: &str = "min-len";
+const ARG_SOLUTION_FILE: &str = "solution-file";
+const ARG_CERTIFICATE: &str = "certificate";
+const ARG_CHAIN: &str = "chain";
+const ARG_CHAINS: &str = "chains";
+const ARG_EXACT: &str = "exact";
+const ARG_PATTERN: &str = "pattern";
+const ARG_WEIGHTED: &str = "weighted";
+const ARG_MAX_CHARS: &str = "max-chars";
+const ARG_WORD_COUNT: &str = "word-count";
+const ARG_START_WORD: &str = "start-word";
+
+const SUBCOMMAND_EXPORT: &str = "export";
+const SUBCOMMAND_IMPORT: &str = "import";
+const SUBCOMMAND_VERIFY: &str = "verify";
+const SUBCOMMAND_DECOMPOSE: &str = "decompose";
+
+const SORTED_MODE_ARGS: &[&str] = &[
+    ARG_GRANULARITY,
+    ARG_PIPELINE,
+    ARG_VERBOSE,
+    ARG_CERTIFICATE,
+    ARG_PATTERN,
+    ARG_WEIGHTED,
+    ARG_MAX_CHARS,
+    ARG_WORD_COUNT,
+];
+
+const RANDOM_MODE_ARGS: &[&str] = &[
+    ARG_SINGLE_THREADED,
+    ARG_LEARNING_RATE,
+    ARG_SELECTION_EXPONENT,
+    ARG_EXPLORATION_RATE,
+    ARG_DECAY_INTERVAL,
+    ARG_DECAY,
+    ARG_SHARE_INTERVAL,
+];
+
+const ANNEALING_MODE_ARGS: &[&str] = &[ARG_TEMPERATURE, ARG_FINAL_TEMPERATURE, ARG_TIME_LIMIT];
+
+const PACKING_MODE_ARGS: &[&str] = &[ARG_CHAINS, ARG_EXACT];
+
+const COUNT_MODE_ARGS: &[&str] = &[ARG_START_WORD];
 
 fn main() {
     let matches = App::new("wordchain")
         .author("Markus Webel <m@rkus.online>")
         .about("Finds the longest chain of non-repeating overlapping words in a file (1 word per line)")
-        .arg(Arg::with_name(ARG_WORD_FILE)
-            .index(1)
-            .help("A file with all unique words to be considered, separated by line-breaks")
-            .required(true))
-        .arg(Arg::with_name(ARG_MIN_OVERLAP)
-            .short('o')
-            .long(ARG_MIN_OVERLAP)
-            .help("How many characters at the end/beginning of two words need to match to be considered linkable")
-            .default_value("1"))
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .arg(word_file_arg().multiple_values(true))
+        .args(input_args())
+        .arg(min_overlap_arg())
         .arg(Arg::with_name(ARG_MODE)
             .short('m')
             .long(ARG_MODE)
             .default_value("normal")
-            .possible_values(&Mode::variants())
+            .possible_values(Mode::variants())
             .help("The search algorithm that is used. Normal mode aims for fastest completion, but gives no intermediate results. \
             Quick estimation mode sacrifices execution time for longer intermediate results, which are printed as soon as they are \
             available. Random mode never terminates and uses heuristic search to guess the longest chain. This mode is NOT guaranteed \
-            to find the correct result."))
+            to find the correct result. Annealing mode uses simulated annealing to guess the longest chain within a time limit. This \
+            mode is NOT guaranteed to find the correct result either. Beam mode grows chains level by level and only keeps the most \
+            promising ones. It is deterministic and fast, but NOT guaranteed to find the correct result. Cover mode splits all words \
+            into as few chains as possible, which is exact for up to 20 words. Fullchain mode only checks whether all words fit into a \
+            single chain and stops as soon as it finds one. Pack mode finds several chains that don't share any words. Count mode \
+            counts the chains of each length, which is fast for up to 16 words and takes very long for large word lists."))
         .arg(Arg::with_name(ARG_GRANULARITY)
             .short('g')
             .long(ARG_GRANULARITY)
@@ -54,59 +157,511 @@ fn main() {
             .help("Determines the granularity of tasks that are distributed to each thread. Usually a fairly small value (~6) is enough \
             to get good load balancing, but higher values might be beneficial for large workloads. This argument also affects logging granularity \
             when --verbose is used. If this argument is omitted, a default value will be used. This argument is not permitted in random mode."))
+        .arg(Arg::with_name(ARG_PIPELINE)
+            .short('p')
+            .long(ARG_PIPELINE)
+            .takes_value(true)
+            .help("Searches up to this many start words concurrently instead of one after another. This keeps all threads busy \
+            at the end of each start word, but later start words might not be able to profit from the estimates of the ones that \
+            are still running. The result is the same either way. This argument is not permitted in random mode."))
+        .arg(Arg::with_name(ARG_SEED)
+            .long(ARG_SEED)
+            .takes_value(true)
+            .help("Seeds the random number generators used in random mode, so that a search can be repeated. Each thread \
+            derives its own generator from this value. This argument is only permitted in random and annealing mode."))
+        .arg(Arg::with_name(ARG_SINGLE_THREADED)
+            .long(ARG_SINGLE_THREADED)
+            .help("Runs the random search on a single thread. Together with --seed, this makes the search fully reproducible. \
+            This argument is only permitted in random mode."))
+        .arg(Arg::with_name(ARG_LEARNING_RATE)
+            .long(ARG_LEARNING_RATE)
+            .takes_value(true)
+            .help("How strongly each new chain influences the learned average chain lengths in random mode. Must be larger \
+            than 0 and at most 1. Defaults to 0.05."))
+        .arg(Arg::with_name(ARG_SELECTION_EXPONENT)
+            .long(ARG_SELECTION_EXPONENT)
+            .takes_value(true)
+            .help("In random mode, words are picked with a likelihood proportional to their average chain length raised to \
+            this power. 0 picks uniformly, higher values focus on known good words. Defaults to 1."))
+        .arg(Arg::with_name(ARG_EXPLORATION_RATE)
+            .long(ARG_EXPLORATION_RATE)
+            .takes_value(true)
+            .help("Probability of picking a word uniformly at random instead of by its learned average in random mode. \
+            Defaults to 0."))
+        .arg(Arg::with_name(ARG_DECAY_INTERVAL)
+            .long(ARG_DECAY_INTERVAL)
+            .takes_value(true)
+            .help("In random mode, lets all learned averages decay towards their initial value every this many chains. \
+            If omitted, averages never decay."))
+        .arg(Arg::with_name(ARG_DECAY)
+            .long(ARG_DECAY)
+            .takes_value(true)
+            .requires(ARG_DECAY_INTERVAL)
+            .help("Fraction between 0 and 1 by which learned averages decay at each decay interval. 1 resets them \
+            completely. Defaults to 1."))
+        .arg(Arg::with_name(ARG_SHARE_INTERVAL)
+            .long(ARG_SHARE_INTERVAL)
+            .takes_value(true)
+            .help("In random mode, every thread merges its learned averages with those of the other threads every this many \
+            chains. If omitted, all threads learn independently."))
+        .arg(Arg::with_name(ARG_TEMPERATURE)
+            .long(ARG_TEMPERATURE)
+            .takes_value(true)
+            .help("Initial temperature in annealing mode. At higher temperatures, moves that shorten the chain are accepted \
+            more often. Defaults to 2."))
+        .arg(Arg::with_name(ARG_FINAL_TEMPERATURE)
+            .long(ARG_FINAL_TEMPERATURE)
+            .takes_value(true)
+            .help("Temperature at the end of the time limit in annealing mode. The temperature decreases exponentially towards \
+            this value. Defaults to 0.05."))
+        .arg(Arg::with_name(ARG_TIME_LIMIT)
+            .long(ARG_TIME_LIMIT)
+            .takes_value(true)
+            .help("How many seconds annealing mode runs before it reports the longest chain found. Defaults to 10."))
+        .arg(Arg::with_name(ARG_BEAM_WIDTH)
+            .long(ARG_BEAM_WIDTH)
+            .takes_value(true)
+            .help("How many partial chains beam mode keeps at each level. Larger values find longer chains, but take more \
+            time and memory. Defaults to 1000."))
+        .arg(Arg::with_name(ARG_CHAINS)
+            .long(ARG_CHAINS)
+            .takes_value(true)
+            .help("How many chains pack mode looks for. Defaults to 3."))
+        .arg(Arg::with_name(ARG_EXACT)
+            .long(ARG_EXACT)
+            .help("Makes pack mode find the chains that use the most words together, instead of taking the longest chain \
+            from the remaining words each time. Limited to 20 words."))
+        .arg(Arg::with_name(ARG_START_WORD)
+            .long(ARG_START_WORD)
+            .takes_value(true)
+            .value_name("WORD")
+            .help("Makes count mode only count the chains that start with this word."))
+        .arg(Arg::with_name(ARG_CERTIFICATE)
+            .long(ARG_CERTIFICATE)
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Writes a certificate to this file, which proves that there is no longer chain than the one found. \
+            It can be checked with the verify command. This argument is only permitted in normal and quick estimation mode."))
+        .arg(Arg::with_name(ARG_PATTERN)
+            .long(ARG_PATTERN)
+            .takes_value(true)
+            .help("A pattern over the tags of the words that every chain has to match, like a regular expression where \
+            each tag stands for one word with that tag. For example, 'city (person city)*' alternates between cities and \
+            persons. . stands for any word, ( ) group, | separates alternatives and *, + and ? repeat. Tags can be written \
+            as start:tag or end:tag for readability. This argument is only permitted in normal and quick estimation mode."))
+        .arg(Arg::with_name(ARG_WEIGHTED)
+            .long(ARG_WEIGHTED)
+            .help("Looks for the chain with the highest sum of word weights (weight=<n> in the word file, 1 by default) \
+            instead of the longest chain. This argument is only permitted in normal and quick estimation mode."))
+        .arg(Arg::with_name(ARG_MAX_CHARS)
+            .long(ARG_MAX_CHARS)
+            .takes_value(true)
+            .help("Only allows chains that have at most this many characters once the words are glued together. \
+            This argument is only permitted in normal and quick estimation mode."))
+        .arg(Arg::with_name(ARG_WORD_COUNT)
+            .long(ARG_WORD_COUNT)
+            .takes_value(true)
+            .help("Only allows chains with exactly this many words. \
+            This argument is only permitted in normal and quick estimation mode."))
         .arg(Arg::with_name(ARG_VERBOSE)
             .short('v')
             .long(ARG_VERBOSE)
             .help("Enables more detailed intermediate output."))
+        .subcommand(App::new(SUBCOMMAND_EXPORT)
+            .about("Writes the search for the longest chain as a model for an external ILP or (Max)SAT solver to stdout")
+            .arg(word_file_arg().multiple_values(true))
+            .args(input_args())
+            .arg(min_overlap_arg())
+            .arg(model_format_arg())
+            .arg(Arg::with_name(ARG_MIN_LEN)
+                .long(ARG_MIN_LEN)
+                .takes_value(true)
+                .required_if_eq(ARG_MODEL_FORMAT, "cnf")
+                .help("For the cnf format: The model is satisfiable if and only if there is a chain with at least this many words")))
+        .subcommand(App::new(SUBCOMMAND_IMPORT)
+            .about("Reads the solution of an external solver for a model written by the export command")
+            .arg(word_file_arg())
+            .arg(Arg::with_name(ARG_SOLUTION_FILE)
+                .index(2)
+                .required(true)
+                .help("The solution file written by the solver"))
+            .args(input_args())
+            .arg(min_overlap_arg())
+            .arg(model_format_arg()))
+        .subcommand(App::new(SUBCOMMAND_VERIFY)
+            .about("Checks a chain against a word file, or a certificate written by a normal or quick estimation search")
+            .arg(word_file_arg()
+                .required(false)
+                .required_unless_present(ARG_CERTIFICATE))
+            .arg(Arg::with_name(ARG_CHAIN)
+                .index(2)
+                .multiple_values(true)
+                .required_unless_present(ARG_CERTIFICATE)
+                .help("The words of the chain, or the whole chain glued together as a single value"))
+            .args(input_args())
+            .arg(min_overlap_arg())
+            .arg(Arg::with_name(ARG_CERTIFICATE)
+                .long(ARG_CERTIFICATE)
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with(ARG_CHAIN)
+                .help("The certificate to check. If a word file is given, it must be the one that was certified")))
+        .subcommand(App::new(SUBCOMMAND_DECOMPOSE)
+            .about("Splits a glued chain, as printed by a search, back into its words")
+            .arg(word_file_arg())
+            .arg(Arg::with_name(ARG_CHAIN)
+                .index(2)
+                .required(true)
+                .help("The glued chain"))
+            .args(input_args())
+            .arg(min_overlap_arg()))
         .get_matches();
 
-    let word_file = matches.value_of(ARG_WORD_FILE).unwrap();
+    match matches.subcommand() {
+        Some((SUBCOMMAND_EXPORT, sub_matches)) => return exec_export(sub_matches),
+        Some((SUBCOMMAND_IMPORT, sub_matches)) => return exec_import(sub_matches),
+        Some((SUBCOMMAND_VERIFY, sub_matches)) => return exec_verify(sub_matches),
+        Some((SUBCOMMAND_DECOMPOSE, sub_matches)) => return exec_decompose(sub_matches),
+        _ => (),
+    }
+
+    let (word_list, min_overlap) = read_word_list_and_overlap(&matches);
+    let WordList {
+        words, metadata, ..
+    } = word_list;
+
+    let mode = value_t_or_exit!(matches, ARG_MODE, Mode);
+
+    match mode {
+        Mode::normal => exec_sorted_search(words, &metadata, min_overlap, mode, &matches),
+        Mode::quickestimate => exec_sorted_search(words, &metadata, min_overlap, mode, &matches),
+        Mode::random => exec_random_search(words, &metadata, min_overlap, &matches),
+        Mode::annealing => exec_annealing_search(words, &metadata, min_overlap, &matches),
+        Mode::beam => exec_beam_search(words, &metadata, min_overlap, &matches),
+        Mode::cover => exec_cover(words, min_overlap, &matches),
+        Mode::fullchain => exec_full_chain(words, min_overlap, &matches),
+        Mode::pack => exec_pack(words, min_overlap, &matches),
+        Mode::count => exec_count(words, min_overlap, &matches),
+    }
+}
 
-    let words = parse_words_file(word_file).unwrap_or_else(|e| {
+fn word_file_arg() -> Arg<'static> {
+    Arg::with_name(ARG_WORD_FILE)
+        .index(1)
+        .help("A file with all unique words to be considered, separated by line-breaks. Use - to read from stdin. Words that \
+        appear more than once are only used once. Each word can be followed by a | and options: weight=<n>, tags=<a,b>, start (only \
+        marked words may start a chain), end (only marked words may end a chain) and required (every chain has to contain \
+        the word). Everything after a # is a comment. CSV, TSV and JSON files are read as well, see --input-format.")
+        .required(true)
+}
+
+fn input_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::with_name(ARG_INPUT_FORMAT)
+            .long(ARG_INPUT_FORMAT)
+            .takes_value(true)
+            .possible_values(WordFileFormat::variants())
+            .help("The format of the word files. If not given, it is guessed from the file extension (.csv, .tsv, .tab \
+            or .json), and everything else is read as text. JSON files contain an array of words, or an array of objects \
+            with a word and optionally a weight and a category."),
+        Arg::with_name(ARG_COLUMN)
+            .long(ARG_COLUMN)
+            .takes_value(true)
+            .value_name("COLUMN")
+            .help("The column with the words in CSV and TSV files, given by its position starting at 1 or by its name \
+            in the header row. Files are expected to start with a header row if any column is given by name. For JSON \
+            objects, this is the key of the word. [default: 1, or word for JSON]"),
+        Arg::with_name(ARG_WEIGHT_COLUMN)
+            .long(ARG_WEIGHT_COLUMN)
+            .takes_value(true)
+            .value_name("COLUMN")
+            .help("The column with the weights of the words, like --column [default: none, or weight for JSON]"),
+        Arg::with_name(ARG_CATEGORY_COLUMN)
+            .long(ARG_CATEGORY_COLUMN)
+            .takes_value(true)
+            .value_name("COLUMN")
+            .help("The column with the categories of the words, which become tags, like --column [default: none, \
+            or category for JSON]"),
+        Arg::with_name(ARG_INPUT_POLICY)
+            .long(ARG_INPUT_POLICY)
+            .default_value("warn")
+            .possible_values(Policy::variants())
+            .help("What happens to words that are repeated, only differ in case from another word, or are too short to \
+            overlap with any other word. error stops, warn only removes repeated words, dedupe also removes words that \
+            only differ in case from an earlier word, and drop also removes words that are too short."),
+    ]
+}
+
+fn min_overlap_arg() -> Arg<'static> {
+    Arg::with_name(ARG_MIN_OVERLAP)
+        .short('o')
+        .long(ARG_MIN_OVERLAP)
+        .help("How many characters at the end/beginning of two words need to match to be considered linkable")
+        .default_value("1")
+}
+
+fn model_format_arg() -> Arg<'static> {
+    Arg::with_name(ARG_MODEL_FORMAT)
+        .short('f')
+        .long(ARG_MODEL_FORMAT)
+        .default_value("lp")
+        .possible_values(ModelFormat::variants())
+        .help("The format of the model. lp and mps are integer linear programs, cnf is a satisfiability problem \
+        and wcnf is a weighted partial MaxSAT problem.")
+}
+
+fn read_words_and_overlap(matches: &clap::ArgMatches) -> (Vec<String>, usize) {
+    let (word_list, min_overlap) = read_word_list_and_overlap(matches);
+
+    (word_list.words, min_overlap)
+}
+
+fn read_word_list_and_overlap(matches: &clap::ArgMatches) -> (WordList, usize) {
+    let paths = matches.values_of(ARG_WORD_FILE).unwrap().collect::<Vec<_>>();
+
+    let input_config = InputConfig {
+        format: matches
+            .value_of(ARG_INPUT_FORMAT)
+            .map(|_| match value_t_or_exit!(matches, ARG_INPUT_FORMAT, WordFileFormat) {
+                WordFileFormat::text => InputFormat::Text,
+                WordFileFormat::csv => InputFormat::Csv,
+                WordFileFormat::tsv => InputFormat::Tsv,
+                WordFileFormat::json => InputFormat::Json,
+            }),
+        word_column: matches.value_of(ARG_COLUMN),
+        weight_column: matches.value_of(ARG_WEIGHT_COLUMN),
+        category_column: matches.value_of(ARG_CATEGORY_COLUMN),
+    };
+
+    let mut word_list = wordchain::read_word_files(&paths, &input_config).unwrap_or_else(|e| {
         panic!("ERROR: Could not read word file ({})", e);
     });
 
     let min_overlap = value_t_or_exit!(matches, ARG_MIN_OVERLAP, usize);
 
-    let mode = value_t_or_exit!(matches, ARG_MODE, Mode);
+    let policy = match value_t_or_exit!(matches, ARG_INPUT_POLICY, Policy) {
+        Policy::error => InputPolicy::Error,
+        Policy::warn => InputPolicy::Warn,
+        Policy::dedupe => InputPolicy::Dedupe,
+        Policy::drop => InputPolicy::Drop,
+    };
 
-    match mode {
-        Mode::normal => exec_sorted_search(words, min_overlap, mode, &matches),
-        Mode::quickestimate => exec_sorted_search(words, min_overlap, mode, &matches),
-        Mode::random => exec_random_search(words, min_overlap, &matches),
+    match word_list.sanitize(min_overlap, policy) {
+        Ok(problems) => {
+            for problem in problems {
+                eprintln!("WARNING: {}", problem);
+            }
+        }
+        Err(problems) => {
+            for problem in &problems {
+                eprintln!("ERROR: {}", problem);
+            }
+
+            panic!(
+                "ERROR: The word file has {} problem(s). Fix them or choose another --{}.",
+                problems.len(),
+                ARG_INPUT_POLICY
+            );
+        }
     }
+
+    (word_list, min_overlap)
+}
+
+/// `min_len` is only used by the cnf format, and only when exporting
+fn read_model_format(matches: &clap::ArgMatches, min_len: usize) -> ExportFormat {
+    match value_t_or_exit!(matches, ARG_MODEL_FORMAT, ModelFormat) {
+        ModelFormat::lp => ExportFormat::Lp,
+        ModelFormat::mps => ExportFormat::Mps,
+        ModelFormat::cnf => ExportFormat::Cnf { min_len },
+        ModelFormat::wcnf => ExportFormat::Wcnf,
+    }
+}
+
+fn exec_export(matches: &clap::ArgMatches) {
+    let (words, min_overlap) = read_words_and_overlap(matches);
+    let format = read_model_format(matches, value_or_default(matches, ARG_MIN_LEN, 1));
+
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+
+    wordchain::export_model(words, min_overlap, &format, &mut writer)
+        .and_then(|_| writer.flush())
+        .unwrap_or_else(|err| {
+            panic!("ERROR: Could not export model ({})", err);
+        });
+}
+
+fn exec_import(matches: &clap::ArgMatches) {
+    let (words, min_overlap) = read_words_and_overlap(matches);
+    let format = read_model_format(matches, 1);
+
+    let mut solution = String::new();
+
+    File::open(matches.value_of(ARG_SOLUTION_FILE).unwrap())
+        .and_then(|mut file| file.read_to_string(&mut solution))
+        .unwrap_or_else(|e| {
+            panic!("ERROR: Could not read solution file ({})", e);
+        });
+
+    let chain_info = wordchain::import_solution(words, min_overlap, &format, &solution)
+        .unwrap_or_else(|err| {
+            panic!("ERROR: {}", err);
+        });
+
+    println!("Chain ({}): {}", chain_info.len, chain_info.chain);
 }
 
-fn parse_words_file(path: &str) -> Result<Vec<String>, io::Error> {
+fn exec_verify(matches: &clap::ArgMatches) {
+    if !matches.is_present(ARG_CERTIFICATE) {
+        return exec_verify_chain(matches);
+    }
+
     let mut content = String::new();
 
-    File::open(path)?.read_to_string(&mut content)?;
+    File::open(matches.value_of(ARG_CERTIFICATE).unwrap())
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .unwrap_or_else(|e| {
+            panic!("ERROR: Could not read certificate ({})", e);
+        });
 
-    Ok(content
-        .lines()
-        .filter(|s| !s.is_empty())
-        .map(ToOwned::to_owned)
-        .collect())
+    let certificate = Certificate::parse(&content).unwrap_or_else(|err| {
+        panic!("ERROR: {}", err);
+    });
+
+    println!(
+        "Certified word list: {} words, min overlap {}",
+        certificate.words.len(),
+        certificate.min_overlap
+    );
+
+    if matches.is_present(ARG_WORD_FILE) {
+        let (words, min_overlap) = read_words_and_overlap(matches);
+
+        certificate
+            .check_words(&words, min_overlap)
+            .unwrap_or_else(|err| {
+                panic!("ERROR: {}", err);
+            });
+    }
+
+    let before = Instant::now();
+
+    let chain_info = certificate.verify().unwrap_or_else(|err| {
+        panic!("ERROR: {}", err);
+    });
+
+    let duration = before.elapsed();
+
+    println!(
+        "Verified certificate in {}.{} s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+
+    println!(
+        "No chain is longer than ({}): {}",
+        chain_info.len, chain_info.chain
+    );
 }
 
-fn exec_sorted_search(
-    words: Vec<String>,
-    min_overlap: usize,
-    mode: Mode,
-    matches: &clap::ArgMatches,
-) {
+fn exec_verify_chain(matches: &clap::ArgMatches) {
+    let (words, min_overlap) = read_words_and_overlap(matches);
+
+    let chain = matches
+        .values_of(ARG_CHAIN)
+        .unwrap()
+        .map(|word| word.trim().to_owned())
+        .collect::<Vec<_>>();
+
+    let input = if chain.len() == 1 {
+        ChainInput::Glued(&chain[0])
+    } else {
+        ChainInput::Words(&chain)
+    };
+
+    let report = wordchain::verify_chain(&words, min_overlap, &input).unwrap_or_else(|err| {
+        panic!("ERROR: {}", err);
+    });
+
+    println!("Chain ({}): {}", report.words.len(), report.words.join(" "));
+
+    for problem in &report.problems {
+        println!("{}", problem);
+    }
+
+    if report.is_valid() {
+        println!("The chain is valid.");
+    } else {
+        panic!("ERROR: The chain has {} problem(s).", report.problems.len());
+    }
+}
+
+fn exec_decompose(matches: &clap::ArgMatches) {
+    let (words, min_overlap) = read_words_and_overlap(matches);
+
+    let glued = matches.value_of(ARG_CHAIN).unwrap().trim();
+
+    let decompositions = wordchain::decompose_chain(&words, min_overlap, glued)
+        .unwrap_or_else(|err| {
+            panic!("ERROR: {}", err);
+        });
+
+    for decomposition in &decompositions {
+        println!(
+            "Chain ({}): {}",
+            decomposition.words.len(),
+            decomposition.words.join(" ")
+        );
+    }
+
+    match decompositions.len() {
+        0 => panic!("ERROR: The chain can't be split into words from the word file. Use the verify command to see where it breaks."),
+        1 => (),
+        count => println!("The chain is ambiguous, it can be split in {} ways.", count),
+    }
+}
+
+fn read_sorted_search_config(matches: &clap::ArgMatches, certificate: bool) -> SortedSearchConfig {
     let granularity = if matches.is_present(ARG_GRANULARITY) {
         Some(value_t_or_exit!(matches, ARG_GRANULARITY, u8))
     } else {
         None
     };
 
-    let verbose = matches.is_present(ARG_VERBOSE);
+    let pipeline = if matches.is_present(ARG_PIPELINE) {
+        Some(value_t_or_exit!(matches, ARG_PIPELINE, usize))
+    } else {
+        None
+    };
 
-    let search_config = SortedSearchConfig {
+    SortedSearchConfig {
         granularity,
-        verbose,
-    };
+        pipeline,
+        verbose: matches.is_present(ARG_VERBOSE),
+        certificate,
+    }
+}
+
+fn exec_sorted_search(
+    words: Vec<String>,
+    metadata: &[WordMetadata],
+    min_overlap: usize,
+    mode: Mode,
+    matches: &clap::ArgMatches,
+) {
+    let mode_name = mode.to_string();
+
+    reject_args(matches, &[ARG_SEED], &mode_name);
+    reject_args(matches, RANDOM_MODE_ARGS, &mode_name);
+    reject_args(matches, ANNEALING_MODE_ARGS, &mode_name);
+    reject_args(matches, &[ARG_BEAM_WIDTH], &mode_name);
+    reject_args(matches, PACKING_MODE_ARGS, &mode_name);
+    reject_args(matches, COUNT_MODE_ARGS, &mode_name);
+
+    let certificate_file = matches.value_of(ARG_CERTIFICATE);
+
+    let search_config = read_sorted_search_config(matches, certificate_file.is_some());
 
     let lib_mode = match mode {
         Mode::normal => wordchain::Mode::Normal(&search_config),
@@ -114,14 +669,55 @@ fn exec_sorted_search(
         _ => unreachable!(),
     };
 
+    let pattern = matches.value_of(ARG_PATTERN).map(|pattern| {
+        TagPattern::parse(pattern).unwrap_or_else(|e| {
+            panic!("ERROR: Could not read tag pattern ({})", e);
+        })
+    });
+
     let config = Config {
         min_overlap,
+        metadata: Some(metadata),
+        pattern: pattern.as_ref(),
+        objective: if matches.is_present(ARG_WEIGHTED) {
+            Objective::Weight
+        } else {
+            Objective::Length
+        },
+        max_chars: if matches.is_present(ARG_MAX_CHARS) {
+            Some(value_t_or_exit!(matches, ARG_MAX_CHARS, usize))
+        } else {
+            None
+        },
+        word_count: if matches.is_present(ARG_WORD_COUNT) {
+            Some(value_t_or_exit!(matches, ARG_WORD_COUNT, usize))
+        } else {
+            None
+        },
         mode: lib_mode,
     };
 
+    let longest_chain_info = exec_terminating_search(words, &config);
+
+    if let (Some(path), Some(certificate)) = (certificate_file, longest_chain_info.certificate) {
+        File::create(path)
+            .and_then(|file| {
+                let mut writer = io::BufWriter::new(file);
+                certificate.write(&mut writer)?;
+                writer.flush()
+            })
+            .unwrap_or_else(|e| {
+                panic!("ERROR: Could not write certificate ({})", e);
+            });
+
+        println!("Wrote certificate to {}", path);
+    }
+}
+
+fn exec_terminating_search(words: Vec<String>, config: &Config) -> ChainInfo {
     let before = Instant::now();
 
-    let longest_chain_info = wordchain::find_longest_chain(words, &config).unwrap_or_else(|err| {
+    let longest_chain_info = wordchain::find_longest_chain(words, config).unwrap_or_else(|err| {
         panic!("ERROR: {}", err);
     });
 
@@ -133,27 +729,346 @@ fn exec_sorted_search(
         duration.subsec_millis()
     );
 
-    println!(
-        "Longest chain ({}): {}",
-        longest_chain_info.len, longest_chain_info.chain
-    );
+    if config.objective == Objective::Weight {
+        println!(
+            "Best chain ({}, score {}): {}",
+            longest_chain_info.len, longest_chain_info.score, longest_chain_info.chain
+        );
+    } else {
+        println!(
+            "Longest chain ({}): {}",
+            longest_chain_info.len, longest_chain_info.chain
+        );
+    }
+
+    longest_chain_info
 }
 
-fn exec_random_search(words: Vec<String>, min_overlap: usize, matches: &clap::ArgMatches) {
+fn exec_random_search(
+    words: Vec<String>,
+    metadata: &[WordMetadata],
+    min_overlap: usize,
+    matches: &clap::ArgMatches,
+) {
     if matches.is_present(ARG_GRANULARITY) {
         panic!("Cannot specify granularity when operating in random mode");
     }
 
+    if matches.is_present(ARG_PIPELINE) {
+        panic!("Cannot specify pipeline depth when operating in random mode");
+    }
+
     if matches.is_present(ARG_VERBOSE) {
         panic!("Verbose mode is not available when operating in random mode");
     }
 
+    reject_args(
+        matches,
+        &[ARG_PATTERN, ARG_WEIGHTED, ARG_MAX_CHARS, ARG_WORD_COUNT],
+        "random",
+    );
+    reject_args(matches, ANNEALING_MODE_ARGS, "random");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "random");
+    reject_args(matches, PACKING_MODE_ARGS, "random");
+    reject_args(matches, COUNT_MODE_ARGS, "random");
+
+    if matches.is_present(ARG_SINGLE_THREADED) {
+        reject_args(matches, &[ARG_SHARE_INTERVAL], "single-threaded random");
+    }
+
+    let seed = if matches.is_present(ARG_SEED) {
+        Some(value_t_or_exit!(matches, ARG_SEED, u64))
+    } else {
+        None
+    };
+
+    let defaults = RandomSearchConfig::default();
+
+    let search_config = RandomSearchConfig {
+        seed,
+        single_threaded: matches.is_present(ARG_SINGLE_THREADED),
+        learning_rate: value_or_default(matches, ARG_LEARNING_RATE, defaults.learning_rate),
+        selection_exponent: value_or_default(
+            matches,
+            ARG_SELECTION_EXPONENT,
+            defaults.selection_exponent,
+        ),
+        exploration_rate: value_or_default(matches, ARG_EXPLORATION_RATE, defaults.exploration_rate),
+        decay_interval: if matches.is_present(ARG_DECAY_INTERVAL) {
+            Some(value_t_or_exit!(matches, ARG_DECAY_INTERVAL, u64))
+        } else {
+            None
+        },
+        decay: value_or_default(matches, ARG_DECAY, defaults.decay),
+        share_interval: if matches.is_present(ARG_SHARE_INTERVAL) {
+            Some(value_t_or_exit!(matches, ARG_SHARE_INTERVAL, u64))
+        } else {
+            None
+        },
+    };
+
     let config = Config {
         min_overlap,
-        mode: wordchain::Mode::RandomSearch,
+        metadata: Some(metadata),
+        pattern: None,
+        objective: Objective::Length,
+        max_chars: None,
+        word_count: None,
+        mode: wordchain::Mode::RandomSearch(&search_config),
     };
 
     wordchain::find_longest_chain(words, &config).unwrap_or_else(|err| {
         panic!("ERROR: {}", err);
     });
 }
+
+fn exec_annealing_search(
+    words: Vec<String>,
+    metadata: &[WordMetadata],
+    min_overlap: usize,
+    matches: &clap::ArgMatches,
+) {
+    reject_args(matches, SORTED_MODE_ARGS, "annealing");
+    reject_args(matches, RANDOM_MODE_ARGS, "annealing");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "annealing");
+    reject_args(matches, PACKING_MODE_ARGS, "annealing");
+    reject_args(matches, COUNT_MODE_ARGS, "annealing");
+
+    let seed = if matches.is_present(ARG_SEED) {
+        Some(value_t_or_exit!(matches, ARG_SEED, u64))
+    } else {
+        None
+    };
+
+    let time_limit = value_or_default(matches, ARG_TIME_LIMIT, 10f64);
+
+    if !(time_limit > 0f64 && time_limit.is_finite()) {
+        panic!("ERROR: The time limit must be a positive number of seconds");
+    }
+
+    let search_config = AnnealingConfig {
+        seed,
+        initial_temperature: value_or_default(matches, ARG_TEMPERATURE, 2f64),
+        final_temperature: value_or_default(matches, ARG_FINAL_TEMPERATURE, 0.05f64),
+        time_limit: Duration::from_secs_f64(time_limit),
+    };
+
+    let config = Config {
+        min_overlap,
+        metadata: Some(metadata),
+        pattern: None,
+        objective: Objective::Length,
+        max_chars: None,
+        word_count: None,
+        mode: wordchain::Mode::Annealing(&search_config),
+    };
+
+    exec_terminating_search(words, &config);
+}
+
+fn exec_beam_search(
+    words: Vec<String>,
+    metadata: &[WordMetadata],
+    min_overlap: usize,
+    matches: &clap::ArgMatches,
+) {
+    reject_args(matches, SORTED_MODE_ARGS, "beam");
+    reject_args(matches, &[ARG_SEED], "beam");
+    reject_args(matches, RANDOM_MODE_ARGS, "beam");
+    reject_args(matches, ANNEALING_MODE_ARGS, "beam");
+    reject_args(matches, PACKING_MODE_ARGS, "beam");
+    reject_args(matches, COUNT_MODE_ARGS, "beam");
+
+    let config = Config {
+        min_overlap,
+        metadata: Some(metadata),
+        pattern: None,
+        objective: Objective::Length,
+        max_chars: None,
+        word_count: None,
+        mode: wordchain::Mode::Beam {
+            width: value_or_default(matches, ARG_BEAM_WIDTH, 1000),
+        },
+    };
+
+    exec_terminating_search(words, &config);
+}
+
+fn exec_cover(words: Vec<String>, min_overlap: usize, matches: &clap::ArgMatches) {
+    reject_args(matches, SORTED_MODE_ARGS, "cover");
+    reject_args(matches, &[ARG_SEED], "cover");
+    reject_args(matches, RANDOM_MODE_ARGS, "cover");
+    reject_args(matches, ANNEALING_MODE_ARGS, "cover");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "cover");
+    reject_args(matches, PACKING_MODE_ARGS, "cover");
+    reject_args(matches, COUNT_MODE_ARGS, "cover");
+
+    let before = Instant::now();
+
+    let cover = wordchain::find_chain_cover(words, min_overlap).unwrap_or_else(|err| {
+        panic!("ERROR: {}", err);
+    });
+
+    let duration = before.elapsed();
+
+    println!(
+        "Finished search in {}.{} s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+
+    for chain_info in &cover.chains {
+        println!("Chain ({}): {}", chain_info.len, chain_info.chain);
+    }
+
+    match (cover.chains.len(), cover.optimal) {
+        (1, _) => println!("All words can be chained together."),
+        (count, true) => println!(
+            "Not all words can be chained together. {} chains are needed to use every word.",
+            count
+        ),
+        (count, false) => println!(
+            "{} chains use every word. There may be a solution with fewer chains.",
+            count
+        ),
+    }
+}
+
+fn exec_full_chain(words: Vec<String>, min_overlap: usize, matches: &clap::ArgMatches) {
+    reject_args(matches, SORTED_MODE_ARGS, "fullchain");
+    reject_args(matches, &[ARG_SEED], "fullchain");
+    reject_args(matches, RANDOM_MODE_ARGS, "fullchain");
+    reject_args(matches, ANNEALING_MODE_ARGS, "fullchain");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "fullchain");
+    reject_args(matches, PACKING_MODE_ARGS, "fullchain");
+    reject_args(matches, COUNT_MODE_ARGS, "fullchain");
+
+    let before = Instant::now();
+
+    let full_chain = wordchain::find_full_chain(words, min_overlap).unwrap_or_else(|err| {
+        panic!("ERROR: {}", err);
+    });
+
+    let duration = before.elapsed();
+
+    println!(
+        "Finished search in {}.{} s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+
+    match full_chain {
+        FullChain::Found(chain_info) => {
+            println!("Chain ({}): {}", chain_info.len, chain_info.chain);
+            println!("All words can be chained together.");
+        }
+        FullChain::Impossible(reason) => {
+            println!("Not all words can be chained together. {}", reason);
+        }
+    }
+}
+
+fn exec_count(words: Vec<String>, min_overlap: usize, matches: &clap::ArgMatches) {
+    reject_args(matches, SORTED_MODE_ARGS, "count");
+    reject_args(matches, &[ARG_SEED], "count");
+    reject_args(matches, RANDOM_MODE_ARGS, "count");
+    reject_args(matches, ANNEALING_MODE_ARGS, "count");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "count");
+    reject_args(matches, PACKING_MODE_ARGS, "count");
+
+    let before = Instant::now();
+
+    let histogram =
+        wordchain::count_chain_lengths(words, min_overlap, matches.value_of(ARG_START_WORD))
+            .unwrap_or_else(|err| {
+                panic!("ERROR: {}", err);
+            });
+
+    let duration = before.elapsed();
+
+    println!(
+        "Finished counting in {}.{} s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+
+    for (len, count) in histogram.iter().enumerate().skip(1) {
+        println!("Chains of length {}: {}", len, count);
+    }
+
+    println!("Chains in total: {}", histogram.iter().sum::<u64>());
+}
+
+fn exec_pack(words: Vec<String>, min_overlap: usize, matches: &clap::ArgMatches) {
+    reject_args(matches, &[ARG_SEED], "pack");
+    reject_args(matches, RANDOM_MODE_ARGS, "pack");
+    reject_args(matches, ANNEALING_MODE_ARGS, "pack");
+    reject_args(matches, &[ARG_BEAM_WIDTH], "pack");
+    reject_args(matches, COUNT_MODE_ARGS, "pack");
+    reject_args(
+        matches,
+        &[
+            ARG_CERTIFICATE,
+            ARG_PATTERN,
+            ARG_WEIGHTED,
+            ARG_MAX_CHARS,
+            ARG_WORD_COUNT,
+        ],
+        "pack",
+    );
+
+    let search_config = read_sorted_search_config(matches, false);
+
+    let packing_mode = if matches.is_present(ARG_EXACT) {
+        reject_args(matches, SORTED_MODE_ARGS, "exact pack");
+        PackingMode::Exact
+    } else {
+        PackingMode::Greedy(&search_config)
+    };
+
+    let word_count = words.len();
+    let count = value_or_default(matches, ARG_CHAINS, 3);
+
+    let before = Instant::now();
+
+    let chains = wordchain::pack_chains(words, min_overlap, count, &packing_mode)
+        .unwrap_or_else(|err| {
+            panic!("ERROR: {}", err);
+        });
+
+    let duration = before.elapsed();
+
+    println!(
+        "Finished search in {}.{} s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+
+    for chain_info in &chains {
+        println!("Chain ({}): {}", chain_info.len, chain_info.chain);
+    }
+
+    println!(
+        "The chains use {} of {} words.",
+        chains.iter().map(|c| c.len as usize).sum::<usize>(),
+        word_count
+    );
+}
+
+fn reject_args(matches: &clap::ArgMatches, args: &[&str], mode: &str) {
+    if let Some(arg) = args.iter().find(|&&arg| matches.is_present(arg)) {
+        panic!("Cannot specify --{} in {} mode", arg, mode);
+    }
+}
+
+fn value_or_default<T>(matches: &clap::ArgMatches, arg: &str, default: T) -> T
+where
+    T: str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    if matches.is_present(arg) {
+        value_t_or_exit!(matches, arg, T)
+    } else {
+        default
+    }
+}
@@ -0,0 +1,81 @@
+use super::cover::CoverTable;
+use super::sorting::{self, SortingOrder};
+use super::{chain, connectivity, words};
+use crate::{ChainInfo, SortedSearchConfig};
+
+/// Finds the longest chain, removes its words and starts over with the remaining words, until
+/// there are `count` chains or no words are left
+pub fn pack_greedily(
+    mut words: Vec<String>,
+    min_overlap: usize,
+    count: usize,
+    sorted_search_config: &SortedSearchConfig,
+) -> Vec<ChainInfo> {
+    let mut chains = Vec::new();
+
+    while chains.len() < count && !words.is_empty() {
+        let connectivity_map = connectivity::create_connectivity_map(&words, min_overlap);
+
+        let sorted_words =
+            sorting::sort_words(words, &connectivity_map, SortingOrder::ForFasterCompletion);
+
+        let connectivity_index_table =
+            connectivity::create_connectivity_index_table(&sorted_words, &connectivity_map);
+
+        let (longest_chain_indices, _) = chain::find_longest_chain_parallel(
+            &connectivity_index_table,
+            &sorted_words,
+            &vec![1; sorted_words.len()],
+            sorted_search_config,
+            &chain::ChainConstraints::none(sorted_words.len()),
+            &chain::KnownBounds::default(),
+        );
+
+        chains.push(ChainInfo {
+            len: longest_chain_indices.len() as u8,
+            chain: words::pretty_format_index_chain(&sorted_words, &longest_chain_indices),
+            score: longest_chain_indices.len() as u64,
+            certificate: None,
+        });
+
+        let chain_mask = chain::create_chain_mask(&longest_chain_indices);
+
+        words = sorted_words
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| !chain_mask.bit(index))
+            .map(|(_, word)| word)
+            .collect();
+    }
+
+    chains
+}
+
+/// Finds at most `count` chains without shared words that use as many words as possible
+/// together. Only works for up to `cover::EXACT_COVER_LIMIT` words.
+pub fn pack_exactly(
+    words: &[String],
+    connectivity_index_table: &[Vec<u8>],
+    count: usize,
+) -> Vec<ChainInfo> {
+    let table = CoverTable::new(connectivity_index_table);
+
+    let best_mask = (0..1usize << words.len())
+        .filter(|&mask| table.chain_count(mask) as usize <= count)
+        .max_by_key(|mask| mask.count_ones())
+        .unwrap_or(0);
+
+    let mut chains = table.find_chains(best_mask);
+
+    chains.sort_by_key(|chain| std::cmp::Reverse(chain.len()));
+
+    chains
+        .iter()
+        .map(|chain| ChainInfo {
+            len: chain.len() as u8,
+            chain: words::pretty_format_index_chain(words, chain),
+            score: chain.len() as u64,
+            certificate: None,
+        })
+        .collect()
+}
@@ -0,0 +1,539 @@
+use crate::TagPattern;
+use std::collections::HashMap;
+
+/// Patterns that need more automaton states than this for a word list are rejected
+const MAX_STATES: usize = 4096;
+
+/// A node of a nondeterministic automaton that reads one word at a time
+pub enum Node {
+    /// Reads a word that matches the atom and continues at the given node
+    Word(Atom, usize),
+
+    /// Continues at both nodes without reading a word
+    Split(usize, usize),
+
+    /// The chain matches the pattern if it ends here
+    Match,
+}
+
+#[derive(Clone)]
+pub enum Atom {
+    /// `.` matches every word
+    Any,
+
+    Tag(String),
+}
+
+impl Atom {
+    fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Tag(tag) => tags.contains(tag),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Bar,
+    Star,
+    Plus,
+    Question,
+    Name(String),
+}
+
+enum Ast {
+    Atom(Atom),
+    Sequence(Vec<Ast>),
+    Alternation(Vec<Ast>),
+    Repeat(Box<Ast>, Repetition),
+}
+
+enum Repetition {
+    ZeroOrMore,
+    OneOrMore,
+    ZeroOrOne,
+}
+
+impl TagPattern {
+    /// Reads a pattern that every chain has to match as a whole. It works like a regular
+    /// expression where each tag stands for one word with that tag:
+    ///
+    /// ```text
+    /// city (person city)*           alternates between cities and persons
+    /// start:animal (food|animal)* end:animal
+    /// ```
+    ///
+    /// `.` stands for any word, and groups can be repeated with `*`, `+` and `?`. A tag can be
+    /// written as `start:tag` or `end:tag` for readability, which means the same as `tag`.
+    pub fn parse(pattern: &str) -> Result<TagPattern, &'static str> {
+        let mut parser = Parser {
+            tokens: tokenize(pattern)?,
+            position: 0,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err("The tag pattern is empty.");
+        }
+
+        let ast = parser.parse_alternation()?;
+
+        if parser.position < parser.tokens.len() {
+            return Err("The tag pattern closes a group that was never opened.");
+        }
+
+        let mut nodes = vec![Node::Match];
+        let start = compile(&ast, 0, &mut nodes);
+
+        Ok(TagPattern { nodes, start })
+    }
+}
+
+fn tokenize(pattern: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '|' => Token::Bar,
+            '*' => Token::Star,
+            '+' => Token::Plus,
+            '?' => Token::Question,
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            _ => {
+                let mut name = String::new();
+
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()|*+?".contains(c) {
+                        break;
+                    }
+
+                    name.push(c);
+                    chars.next();
+                }
+
+                let tag = name
+                    .strip_prefix("start:")
+                    .or_else(|| name.strip_prefix("end:"))
+                    .unwrap_or(&name);
+
+                if tag.is_empty() {
+                    return Err("A tag in the tag pattern is empty.");
+                }
+
+                tokens.push(Token::Name(tag.to_owned()));
+                continue;
+            }
+        };
+
+        tokens.push(token);
+        chars.next();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_alternation(&mut self) -> Result<Ast, &'static str> {
+        let mut branches = vec![self.parse_sequence()?];
+
+        while self.peek() == Some(&Token::Bar) {
+            self.position += 1;
+            branches.push(self.parse_sequence()?);
+        }
+
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Ast::Alternation(branches))
+        }
+    }
+
+    fn parse_sequence(&mut self) -> Result<Ast, &'static str> {
+        let mut items = Vec::new();
+
+        loop {
+            match self.peek() {
+                None | Some(Token::Bar) | Some(Token::Close) => return Ok(Ast::Sequence(items)),
+                Some(Token::Star) | Some(Token::Plus) | Some(Token::Question) => {
+                    return Err("A repetition in the tag pattern has to follow a tag or a group.")
+                }
+                _ => items.push(self.parse_repeat()?),
+            }
+        }
+    }
+
+    fn parse_repeat(&mut self) -> Result<Ast, &'static str> {
+        let mut ast = self.parse_atom()?;
+
+        loop {
+            let repetition = match self.peek() {
+                Some(Token::Star) => Repetition::ZeroOrMore,
+                Some(Token::Plus) => Repetition::OneOrMore,
+                Some(Token::Question) => Repetition::ZeroOrOne,
+                _ => return Ok(ast),
+            };
+
+            self.position += 1;
+            ast = Ast::Repeat(Box::new(ast), repetition);
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Ast, &'static str> {
+        self.position += 1;
+
+        match &self.tokens[self.position - 1] {
+            Token::Name(name) if name == "." => Ok(Ast::Atom(Atom::Any)),
+            Token::Name(name) => Ok(Ast::Atom(Atom::Tag(name.clone()))),
+            Token::Open => {
+                let inner = self.parse_alternation()?;
+
+                if self.peek() != Some(&Token::Close) {
+                    return Err("The tag pattern opens a group that is never closed.");
+                }
+
+                self.position += 1;
+
+                Ok(inner)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Adds the nodes for `ast` and returns the node to enter it at. After `ast`, the automaton
+/// continues at `next`.
+fn compile(ast: &Ast, next: usize, nodes: &mut Vec<Node>) -> usize {
+    match ast {
+        Ast::Atom(atom) => {
+            nodes.push(Node::Word(atom.clone(), next));
+            nodes.len() - 1
+        }
+        Ast::Sequence(items) => items
+            .iter()
+            .rev()
+            .fold(next, |entry, item| compile(item, entry, nodes)),
+        Ast::Alternation(branches) => {
+            let entries = branches
+                .iter()
+                .map(|branch| compile(branch, next, nodes))
+                .collect::<Vec<_>>();
+
+            entries
+                .into_iter()
+                .rev()
+                .reduce(|rest, entry| {
+                    nodes.push(Node::Split(entry, rest));
+                    nodes.len() - 1
+                })
+                .unwrap()
+        }
+        Ast::Repeat(inner, Repetition::ZeroOrOne) => {
+            let entry = compile(inner, next, nodes);
+
+            nodes.push(Node::Split(entry, next));
+            nodes.len() - 1
+        }
+        Ast::Repeat(inner, repetition) => {
+            // The loop is entered before `inner` is compiled, so it starts out as a placeholder
+            nodes.push(Node::Split(next, next));
+            let repeat = nodes.len() - 1;
+
+            let entry = compile(inner, repeat, nodes);
+            nodes[repeat] = Node::Split(entry, next);
+
+            match repetition {
+                Repetition::OneOrMore => entry,
+                _ => repeat,
+            }
+        }
+    }
+}
+
+/// A deterministic version of a `TagPattern` for one word list. State 0 is dead, which means
+/// that no chain that reached it can match the pattern anymore.
+pub struct TagAutomaton {
+    word_count: usize,
+
+    /// The next state for each state and word, at `state * word_count + word`
+    transitions: Vec<u16>,
+
+    accepting: Vec<bool>,
+    start: u16,
+}
+
+impl TagAutomaton {
+    pub const DEAD: u16 = 0;
+
+    /// Builds the automaton for words with the given tags
+    pub fn new(pattern: &TagPattern, tags: &[&[String]]) -> Result<TagAutomaton, &'static str> {
+        let word_count = tags.len();
+
+        // Words with the same tags always lead to the same state
+        let mut class_of_tags = HashMap::new();
+
+        let word_classes = tags
+            .iter()
+            .map(|&word_tags| {
+                let mut sorted = word_tags.to_vec();
+                sorted.sort();
+                sorted.dedup();
+
+                let class_count = class_of_tags.len();
+                *class_of_tags.entry(sorted).or_insert(class_count)
+            })
+            .collect::<Vec<_>>();
+
+        let mut classes = vec![Vec::new(); class_of_tags.len()];
+
+        for (class_tags, class) in class_of_tags {
+            classes[class] = class_tags;
+        }
+
+        let mut state_of_set = HashMap::new();
+        let mut sets = vec![Vec::new()];
+        state_of_set.insert(Vec::new(), TagAutomaton::DEAD);
+
+        let start_set = closure(pattern, vec![pattern.start]);
+        let start = sets.len() as u16;
+        state_of_set.insert(start_set.clone(), start);
+        sets.push(start_set);
+
+        let mut class_transitions = vec![vec![TagAutomaton::DEAD; classes.len()]];
+
+        // States are numbered in the order they are found, so the next one to explore is simply
+        // the next number
+        let mut state = 1;
+
+        while state < sets.len() {
+            let mut transitions = Vec::with_capacity(classes.len());
+
+            for class_tags in &classes {
+                let next_nodes = sets[state]
+                    .iter()
+                    .filter_map(|&node| match &pattern.nodes[node] {
+                        Node::Word(atom, next) if atom.matches(class_tags) => Some(*next),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                let next_set = closure(pattern, next_nodes);
+
+                let next_state = match state_of_set.get(&next_set) {
+                    Some(&next_state) => next_state,
+                    None => {
+                        if sets.len() >= MAX_STATES {
+                            return Err("The tag pattern is too complicated for this word list.");
+                        }
+
+                        let next_state = sets.len() as u16;
+                        state_of_set.insert(next_set.clone(), next_state);
+                        sets.push(next_set);
+                        next_state
+                    }
+                };
+
+                transitions.push(next_state);
+            }
+
+            class_transitions.push(transitions);
+            state += 1;
+        }
+
+        let transitions = class_transitions
+            .iter()
+            .flat_map(|transitions| word_classes.iter().map(move |&class| transitions[class]))
+            .collect();
+
+        // Node 0 is the match node
+        let accepting = sets.iter().map(|set| set.first() == Some(&0)).collect();
+
+        Ok(TagAutomaton {
+            word_count,
+            transitions,
+            accepting,
+            start,
+        })
+    }
+
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn next(&self, state: u16, word: u8) -> u16 {
+        self.transitions[state as usize * self.word_count + word as usize]
+    }
+
+    pub fn is_accepting(&self, state: u16) -> bool {
+        self.accepting[state as usize]
+    }
+}
+
+/// All nodes that can be reached from `nodes` without reading a word, without split nodes and
+/// sorted, so that equal sets compare equal
+fn closure(pattern: &TagPattern, mut nodes: Vec<usize>) -> Vec<usize> {
+    let mut visited = vec![false; pattern.nodes.len()];
+    let mut set = Vec::new();
+
+    while let Some(node) = nodes.pop() {
+        if visited[node] {
+            continue;
+        }
+
+        visited[node] = true;
+
+        match pattern.nodes[node] {
+            Node::Split(a, b) => {
+                nodes.push(a);
+                nodes.push(b);
+            }
+            _ => set.push(node),
+        }
+    }
+
+    set.sort_unstable();
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(pattern: &str) -> &'static str {
+        match TagPattern::parse(pattern) {
+            Ok(_) => panic!("{} should be rejected", pattern),
+            Err(error) => error,
+        }
+    }
+
+    /// Whether a chain of words with the given comma separated tags matches `pattern`
+    fn matches(pattern: &str, chain: &[&str]) -> bool {
+        let pattern = TagPattern::parse(pattern).unwrap();
+
+        let tags = chain
+            .iter()
+            .map(|tags| {
+                tags.split(',')
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| tag.to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let tag_slices = tags.iter().map(|tags| tags.as_slice()).collect::<Vec<_>>();
+        let automaton = TagAutomaton::new(&pattern, &tag_slices).unwrap();
+
+        let state = (0..chain.len()).fold(automaton.start(), |state, word| {
+            automaton.next(state, word as u8)
+        });
+
+        automaton.is_accepting(state)
+    }
+
+    #[test]
+    fn malformed_patterns_are_rejected() {
+        assert_eq!(parse_error(""), "The tag pattern is empty.");
+        assert_eq!(parse_error("   "), "The tag pattern is empty.");
+        assert_eq!(
+            parse_error("city start:"),
+            "A tag in the tag pattern is empty."
+        );
+        assert_eq!(
+            parse_error("city (person"),
+            "The tag pattern opens a group that is never closed."
+        );
+        assert_eq!(
+            parse_error("city) person"),
+            "The tag pattern closes a group that was never opened."
+        );
+        assert_eq!(
+            parse_error("* city"),
+            "A repetition in the tag pattern has to follow a tag or a group."
+        );
+        assert_eq!(
+            parse_error("city (|*)"),
+            "A repetition in the tag pattern has to follow a tag or a group."
+        );
+    }
+
+    #[test]
+    fn sequences_and_repetitions() {
+        assert!(matches("city person", &["city", "person"]));
+        assert!(!matches("city person", &["person", "city"]));
+        assert!(!matches("city person", &["city"]));
+        assert!(matches(
+            "start:city . end:city",
+            &["city", "", "city,person"]
+        ));
+
+        assert!(matches("city (person city)*", &["city"]));
+        assert!(matches("city (person city)*", &["city", "person", "city"]));
+        assert!(!matches("city (person city)*", &["city", "person"]));
+
+        assert!(!matches("city+", &[]));
+        assert!(matches("city+", &["city", "city", "city"]));
+        assert!(matches("city? person", &["person"]));
+        assert!(!matches("city? person", &["city", "city", "person"]));
+        assert!(matches("(city*)*", &[]));
+    }
+
+    #[test]
+    fn alternation() {
+        let pattern = "start:animal (food|animal)* end:animal";
+
+        assert!(!matches(pattern, &["animal"]));
+        assert!(matches(pattern, &["animal", "animal"]));
+        assert!(matches(pattern, &["animal", "food", "animal"]));
+        assert!(matches(
+            pattern,
+            &["animal", "animal", "food", "food", "animal"]
+        ));
+        assert!(!matches(pattern, &["animal", "city", "animal"]));
+        assert!(!matches(pattern, &["animal", "food"]));
+
+        // Alternation binds weaker than sequences
+        assert!(matches("city person | food", &["food"]));
+        assert!(matches("city person | food", &["city", "person"]));
+        assert!(!matches("city person | food", &["city", "food"]));
+        assert!(matches("city (person | food)", &["city", "food"]));
+
+        // Empty branches match no word at all
+        assert!(matches("city (person|)", &["city"]));
+        assert!(matches("(|city|person) food", &["person", "food"]));
+    }
+
+    #[test]
+    fn states_are_limited() {
+        // Needs a state for every combination of the last 13 words
+        let pattern = format!("(a|b)* a {}", ". ".repeat(12));
+        let tags = [vec!["a".to_owned()], vec!["b".to_owned()]];
+        let tags = tags.iter().map(|tags| tags.as_slice()).collect::<Vec<_>>();
+
+        match TagAutomaton::new(&TagPattern::parse(&pattern).unwrap(), &tags) {
+            Ok(_) => panic!("the automaton should be too large"),
+            Err(error) => assert_eq!(
+                error,
+                "The tag pattern is too complicated for this word list."
+            ),
+        }
+
+        let pattern = format!("(a|b)* a {}", ". ".repeat(4));
+        assert!(TagAutomaton::new(&TagPattern::parse(&pattern).unwrap(), &tags).is_ok());
+    }
+}
@@ -1,126 +1,232 @@
-use crate::U256;
+use crate::{RandomSearchConfig, U256};
 
+use super::local_search::LocalSearch;
 use super::words::pretty_format_index_chain;
 use rand::prelude::*;
 use rand::rngs::SmallRng;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
-pub fn find_longest(connectivity_index_table: Vec<Vec<u8>>, words: Vec<String>) {
-    // Setup shared resources
-    let longest_len_global = Arc::new(Mutex::new(0usize)); // PERF: Maybe u8?
-    let mut starter_table = create_starter_table(&connectivity_index_table);
-    let mut follower_table = create_follower_table(&connectivity_index_table);
-    let words = Arc::new(words);
+pub fn find_longest(
+    connectivity_index_table: Vec<Vec<u8>>,
+    words: Vec<String>,
+    config: &RandomSearchConfig,
+) {
+    let thread_count = if config.single_threaded {
+        1
+    } else {
+        num_cpus::get()
+    };
 
-    for _ in 1..num_cpus::get() {
+    // Setup shared resources
+    let mut walk = RandomWalk::new(&connectivity_index_table);
+
+    let shared = Arc::new(SharedResources {
+        longest_len_global: Mutex::new(0),
+        // Sharing only makes sense if there is someone to share with
+        shared_tables: match config.share_interval {
+            Some(_) if thread_count > 1 => Some(Mutex::new(SharedTables {
+                starter_table: walk.starter_table.clone(),
+                follower_table: walk.follower_table.clone(),
+            })),
+            _ => None,
+        },
+        local_search: LocalSearch::new(&connectivity_index_table),
+        words,
+        config: config.clone(),
+    });
+
+    for thread_index in 1..thread_count {
         // Copy/clone shared resources
-        let longest_len_global = Arc::clone(&longest_len_global);
-        let mut starter_table = starter_table.clone();
-        let mut follower_table = follower_table.clone();
-        let words = Arc::clone(&words);
-        let mut rng = SmallRng::from_entropy();
+        let shared = Arc::clone(&shared);
+        let mut walk = walk.clone();
+        let mut rng = create_rng(config.seed, thread_index);
 
         // Start search thread
-        thread::spawn(move || {
-            find_longest_thread(
-                &*longest_len_global,
-                &mut starter_table,
-                &mut follower_table,
-                &*words,
-                &mut rng,
-            )
-        });
+        thread::spawn(move || find_longest_thread(&shared, &mut walk, &mut rng));
     }
 
     // Start search on this thread
-    let mut rng = SmallRng::from_entropy();
-
-    find_longest_thread(
-        &*longest_len_global,
-        &mut starter_table,
-        &mut follower_table,
-        &*words,
-        &mut rng,
-    );
-}
-
-fn find_longest_thread<R>(
-    longest_len_global: &Mutex<usize>,
-    starter_table: &mut Vec<Follower>,
-    follower_table: &mut Vec<Vec<Follower>>,
-    words: &Vec<String>,
-    rng: &mut R,
-) where
+    let mut rng = create_rng(config.seed, 0);
+
+    find_longest_thread(&shared, &mut walk, &mut rng);
+}
+
+/// Resources that all search threads have access to
+struct SharedResources {
+    longest_len_global: Mutex<usize>, // PERF: Maybe u8?
+    shared_tables: Option<Mutex<SharedTables>>,
+    local_search: LocalSearch,
+    words: Vec<String>,
+    config: RandomSearchConfig,
+}
+
+/// Every thread gets its own generator, derived from the seed and the thread index, so that each
+/// thread explores a different but reproducible sequence of chains
+fn create_rng(seed: Option<u64>, thread_index: usize) -> SmallRng {
+    match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed.wrapping_add(thread_index as u64)),
+        None => SmallRng::from_entropy(),
+    }
+}
+
+fn find_longest_thread<R>(shared: &SharedResources, walk: &mut RandomWalk, rng: &mut R)
+where
     R: Rng,
 {
-    // One-time setup
-    let mut average_chain_lens_sum = starter_table.len() as f32;
-    let mut longest_len_local = 0usize; // PERF: Maybe u8?
+    let config = &shared.config;
 
-    let mut chain: Vec<u8> = Vec::new(); // PERF: Guess size
+    let mut longest_len_local = 0usize; // PERF: Maybe u8?
 
     loop {
-        // Reset per-chain resources
-        let mut latest =
-            pick_random_follower_with_sum(&*starter_table, average_chain_lens_sum, rng);
+        walk.build_chain(config, rng);
+
+        // Check for longest chain TODO: Factor this thing out
+        if walk.chain.len() > longest_len_local {
+            // Promising chains are worth polishing before they are reported and learned from
+            shared.local_search.improve_chain(&mut walk.chain);
+
+            let mut longest_global = shared.longest_len_global.lock().unwrap();
+
+            if walk.chain.len() > *longest_global {
+                println!(
+                    "Longest chain ({}): {}",
+                    walk.chain.len(),
+                    pretty_format_index_chain(&shared.words, &walk.chain)
+                );
+
+                *longest_global = walk.chain.len();
+            }
+
+            longest_len_local = *longest_global;
+        }
+
+        walk.learn(config, shared.shared_tables.as_ref());
+    }
+}
+
+/// Everything a single search thread has learned so far, along with the chain it built last
+#[derive(Clone)]
+struct RandomWalk {
+    starter_table: Vec<Follower>,
+    follower_table: Vec<Vec<Follower>>,
+    largest_starter_average: f32,
+    selection_weight_sum: f64,
+    chain_count: u64,
+    chain: Vec<u8>, // PERF: Guess size
+}
+
+impl RandomWalk {
+    fn new(connectivity_index_table: &[Vec<u8>]) -> RandomWalk {
+        RandomWalk {
+            starter_table: create_starter_table(connectivity_index_table),
+            follower_table: create_follower_table(connectivity_index_table),
+            largest_starter_average: 1f32,
+            selection_weight_sum: connectivity_index_table.len() as f64,
+            chain_count: 0,
+            chain: Vec::new(),
+        }
+    }
+
+    /// Replaces `chain` with a new chain, picking each word by its learned average
+    fn build_chain<R: Rng>(&mut self, config: &RandomSearchConfig, rng: &mut R) {
+        let mut latest = if explore(config.exploration_rate, rng) {
+            self.starter_table[rng.gen_range(0..self.starter_table.len())].follower_index
+        } else {
+            pick_random_follower_with_sum(
+                &self.starter_table,
+                self.largest_starter_average,
+                self.selection_weight_sum,
+                config.selection_exponent,
+                rng,
+            )
+        };
 
         let mut chain_mask = U256::zero();
 
-        chain.clear();
-        chain.push(latest);
+        self.chain.clear();
+        self.chain.push(latest);
 
         loop {
             // Chain growing
 
             chain_mask = chain_mask | U256::one() << latest;
 
-            let mut followers = (&mut follower_table[latest as usize])
+            let mut followers = self.follower_table[latest as usize]
                 .iter()
                 .filter(|&follower| !chain_mask.bit(follower.follower_index as usize))
                 .peekable();
 
             if followers.peek().is_some() {
-                latest = pick_random_follower(followers, rng);
-
-                chain.push(latest);
+                latest = if explore(config.exploration_rate, rng) {
+                    let count = followers.clone().count();
+
+                    followers
+                        .nth(rng.gen_range(0..count))
+                        .unwrap()
+                        .follower_index
+                } else {
+                    pick_random_follower(followers, config.selection_exponent, rng)
+                };
+
+                self.chain.push(latest);
             } else {
                 break;
             }
         }
+    }
 
-        // Check for longest chain TODO: Factor this thing out
-        if chain.len() > longest_len_local {
-            let mut longest_global = longest_len_global.lock().unwrap();
-
-            if chain.len() > *longest_global {
-                println!(
-                    "Longest chain ({}): {}",
-                    chain.len(),
-                    pretty_format_index_chain(&words, &chain)
-                );
-
-                *longest_global = chain.len();
-            }
-
-            longest_len_local = *longest_global;
-        }
-
-        // Update per-chain lookups with new evidence
-
-        let chain_flen = chain.len() as f32;
+    /// Updates the averages with the evidence of the last chain
+    fn learn(&mut self, config: &RandomSearchConfig, shared_tables: Option<&Mutex<SharedTables>>) {
+        let chain_flen = self.chain.len() as f32;
 
         // Update starter average length
         rolling_average_update(
-            &mut starter_table[chain[0] as usize].average_chain_len_pair,
+            &mut self.starter_table[self.chain[0] as usize].average_chain_len_pair,
             chain_flen,
+            config.learning_rate,
         );
 
-        // Re-calculate sum of average chain lengths for starters
-        average_chain_lens_sum = starter_table.iter().map(|f| f.average_chain_len_pair).sum();
-
         // ... and the average length of each pair in the chain
-        update_follower_averages(follower_table, &chain, chain_flen);
+        update_follower_averages(
+            &mut self.follower_table,
+            &self.chain,
+            chain_flen,
+            config.learning_rate,
+        );
+
+        // Periodically forget some of the evidence, so that early luck doesn't dominate forever
+        self.chain_count += 1;
+
+        if let Some(interval) = config.decay_interval {
+            if self.chain_count.is_multiple_of(interval) {
+                decay_averages(
+                    &mut self.starter_table,
+                    &mut self.follower_table,
+                    config.decay,
+                );
+            }
+        }
+
+        // ... and let the other threads know what we learned (and vice versa)
+        if let (Some(shared_tables), Some(interval)) = (shared_tables, config.share_interval) {
+            if self.chain_count.is_multiple_of(interval) {
+                merge_shared_tables(
+                    &mut shared_tables.lock().unwrap(),
+                    &mut self.starter_table,
+                    &mut self.follower_table,
+                );
+            }
+        }
+
+        // Re-calculate sum of selection weights for starters
+        self.largest_starter_average = largest_average(&self.starter_table);
+
+        self.selection_weight_sum = self
+            .starter_table
+            .iter()
+            .map(|f| selection_weight(f, self.largest_starter_average, config.selection_exponent))
+            .sum();
     }
 }
 
@@ -132,7 +238,13 @@ struct Follower {
     average_chain_len_pair: f32, // Think about f64
 }
 
-fn create_starter_table(connectivity_index_table: &Vec<Vec<u8>>) -> Vec<Follower> {
+/// Learned averages that are periodically merged between all search threads
+struct SharedTables {
+    starter_table: Vec<Follower>,
+    follower_table: Vec<Vec<Follower>>,
+}
+
+fn create_starter_table(connectivity_index_table: &[Vec<u8>]) -> Vec<Follower> {
     (0..(connectivity_index_table.len() as u8))
         .map(|i| Follower {
             follower_index: i,
@@ -141,7 +253,7 @@ fn create_starter_table(connectivity_index_table: &Vec<Vec<u8>>) -> Vec<Follower
         .collect()
 }
 
-fn create_follower_table(connectivity_index_table: &Vec<Vec<u8>>) -> Vec<Vec<Follower>> {
+fn create_follower_table(connectivity_index_table: &[Vec<u8>]) -> Vec<Vec<Follower>> {
     connectivity_index_table
         .iter()
         .map(|followers| {
@@ -156,23 +268,91 @@ fn create_follower_table(connectivity_index_table: &Vec<Vec<u8>>) -> Vec<Vec<Fol
         .collect()
 }
 
-fn rolling_average_update(current: &mut f32, new_sample: f32) {
-    const CONVERGENCE_RATE: f32 = 0.05; // TODO: Investigate other values
+fn rolling_average_update(current: &mut f32, new_sample: f32, learning_rate: f32) {
+    *current += learning_rate * (new_sample - *current);
+}
+
+/// Moves the shared averages halfway towards the local ones and then adopts them locally. Since
+/// every thread does this in turn, the evidence of all threads accumulates in the shared tables.
+fn merge_shared_tables(
+    shared: &mut SharedTables,
+    starter_table: &mut [Follower],
+    follower_table: &mut [Vec<Follower>],
+) {
+    let shared_followers = shared
+        .starter_table
+        .iter_mut()
+        .chain(shared.follower_table.iter_mut().flatten());
+
+    let local_followers = starter_table
+        .iter_mut()
+        .chain(follower_table.iter_mut().flatten());
+
+    for (shared, local) in shared_followers.zip(local_followers) {
+        debug_assert_eq!(shared.follower_index, local.follower_index);
+
+        shared.average_chain_len_pair =
+            (shared.average_chain_len_pair + local.average_chain_len_pair) / 2f32;
+        local.average_chain_len_pair = shared.average_chain_len_pair;
+    }
+}
+
+/// Pulls all averages back towards their initial value of 1 by the fraction `decay`
+fn decay_averages(starters: &mut [Follower], followers: &mut [Vec<Follower>], decay: f32) {
+    for follower in starters.iter_mut().chain(followers.iter_mut().flatten()) {
+        rolling_average_update(&mut follower.average_chain_len_pair, 1f32, decay);
+    }
+}
 
-    *current = *current + CONVERGENCE_RATE * (new_sample - *current);
+fn explore<R: Rng>(exploration_rate: f64, rng: &mut R) -> bool {
+    exploration_rate > 0f64 && rng.gen_bool(exploration_rate)
+}
+
+/// The likelihood of picking a follower is proportional to its average chain length raised to
+/// `selection_exponent`. Higher exponents favor known good pairs more strongly.
+///
+/// Averages are divided by the largest average among the candidates first, so that large
+/// exponents can't overflow. The best candidate always weighs exactly 1, which keeps the sum of
+/// all weights at 1 or more.
+fn selection_weight(follower: &Follower, largest_average: f32, selection_exponent: f32) -> f64 {
+    let relative = f64::from(follower.average_chain_len_pair) / f64::from(largest_average);
+
+    if selection_exponent == 1f32 {
+        relative
+    } else {
+        relative.powf(f64::from(selection_exponent))
+    }
+}
+
+fn largest_average<'a, I>(followers: I) -> f32
+where
+    I: IntoIterator<Item = &'a Follower>,
+{
+    followers
+        .into_iter()
+        .map(|f| f.average_chain_len_pair)
+        .fold(0f32, f32::max)
 }
 
-fn pick_random_follower_with_sum<'a, I, R>(starters: I, starter_avg_sum: f32, rng: &mut R) -> u8
+fn pick_random_follower_with_sum<'a, I, R>(
+    starters: I,
+    largest_average: f32,
+    weight_sum: f64,
+    selection_exponent: f32,
+    rng: &mut R,
+) -> u8
 where
     I: IntoIterator<Item = &'a Follower>,
     R: Rng,
 {
-    let target = rng.gen_range(0f32..starter_avg_sum);
+    debug_assert!(weight_sum >= 1f64);
+
+    let target = rng.gen_range(0f64..weight_sum);
 
-    let mut acc = 0f32;
+    let mut acc = 0f64;
 
     for follower in starters {
-        let next_acc = acc + follower.average_chain_len_pair;
+        let next_acc = acc + selection_weight(follower, largest_average, selection_exponent);
 
         if next_acc > target {
             return follower.follower_index;
@@ -184,21 +364,34 @@ where
     unreachable!()
 }
 
-fn pick_random_follower<'a, I, R>(followers: I, rng: &mut R) -> u8
+fn pick_random_follower<'a, I, R>(followers: I, selection_exponent: f32, rng: &mut R) -> u8
 where
     I: IntoIterator<Item = &'a Follower> + Clone,
     R: Rng,
 {
-    let avg_sum = followers
+    let largest_average = largest_average(followers.clone());
+
+    let weight_sum = followers
         .clone()
         .into_iter()
-        .map(|f| f.average_chain_len_pair)
+        .map(|f| selection_weight(f, largest_average, selection_exponent))
         .sum();
 
-    pick_random_follower_with_sum(followers, avg_sum, rng)
+    pick_random_follower_with_sum(
+        followers,
+        largest_average,
+        weight_sum,
+        selection_exponent,
+        rng,
+    )
 }
 
-fn update_follower_averages(followers: &mut Vec<Vec<Follower>>, chain: &Vec<u8>, new_sample: f32) {
+fn update_follower_averages(
+    followers: &mut [Vec<Follower>],
+    chain: &[u8],
+    new_sample: f32,
+    learning_rate: f32,
+) {
     for pair in chain.windows(2) {
         if let &[a, b] = pair {
             let a_follower = followers[a as usize]
@@ -206,9 +399,106 @@ fn update_follower_averages(followers: &mut Vec<Vec<Follower>>, chain: &Vec<u8>,
                 .find(|f| f.follower_index == b)
                 .unwrap();
 
-            rolling_average_update(&mut a_follower.average_chain_len_pair, new_sample);
+            rolling_average_update(
+                &mut a_follower.average_chain_len_pair,
+                new_sample,
+                learning_rate,
+            );
         } else {
             panic!("Windowing function failed")
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 16 words, each followed by the next three, wrapping around
+    fn connectivity_index_table() -> Vec<Vec<u8>> {
+        (0..16u8)
+            .map(|i| (1..4).map(|d| (i + d) % 16).collect())
+            .collect()
+    }
+
+    fn walk_chains(config: &RandomSearchConfig, count: usize) -> Vec<Vec<u8>> {
+        let table = connectivity_index_table();
+        let mut walk = RandomWalk::new(&table);
+        let mut rng = create_rng(config.seed, 0);
+
+        (0..count)
+            .map(|_| {
+                walk.build_chain(config, &mut rng);
+                walk.learn(config, None);
+                walk.chain.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn seeded_walks_are_reproducible() {
+        let config = RandomSearchConfig {
+            seed: Some(42),
+            single_threaded: true,
+            exploration_rate: 0.1,
+            decay_interval: Some(50),
+            decay: 0.5,
+            ..RandomSearchConfig::default()
+        };
+
+        let chains = walk_chains(&config, 500);
+
+        assert_eq!(chains, walk_chains(&config, 500));
+
+        let other_seed = RandomSearchConfig {
+            seed: Some(43),
+            ..config.clone()
+        };
+
+        assert_ne!(chains, walk_chains(&other_seed, 500));
+    }
+
+    #[test]
+    fn walks_build_valid_chains() {
+        let table = connectivity_index_table();
+        let config = RandomSearchConfig {
+            seed: Some(7),
+            ..RandomSearchConfig::default()
+        };
+
+        for chain in walk_chains(&config, 200) {
+            let mut used = chain.clone();
+            used.sort_unstable();
+            used.dedup();
+
+            assert_eq!(used.len(), chain.len());
+            assert!(chain
+                .windows(2)
+                .all(|pair| table[pair[0] as usize].contains(&pair[1])));
+        }
+    }
+
+    #[test]
+    fn large_selection_exponents_dont_overflow() {
+        let followers = [(0, 1f32), (1, 200f32), (2, 199f32)]
+            .iter()
+            .map(|&(follower_index, average_chain_len_pair)| Follower {
+                follower_index,
+                average_chain_len_pair,
+            })
+            .collect::<Vec<_>>();
+
+        let mut rng = create_rng(Some(1), 0);
+
+        for &exponent in &[0f32, 1f32, 60f32, 1000f32, 1e30] {
+            for _ in 0..100 {
+                let picked = pick_random_follower(&followers, exponent, &mut rng);
+
+                // Every other weight rounds down to 0
+                if exponent == 1e30 {
+                    assert_eq!(picked, 1);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{InputPolicy, InputProblem, WordList, WordPosition, WordProblem, WordProblemKind};
+use std::collections::HashMap;
+use std::fmt;
+
+pub const EMPTY_WORD: &str = "Words can't be empty.";
+pub const DUPLICATE_WORD: &str = "Every word may only appear once in the word list.";
+const TOO_SHORT_WORD: &str =
+    "A word isn't longer than the minimum overlap, so it can't be chained with any other word.";
+const CASE_DUPLICATE_WORD: &str = "Two words only differ in case.";
+
+/// Finds problems with `words`, each along with the index of the word it is about. `position`
+/// tells where a word came from.
+pub fn find_word_problems<F: Fn(usize) -> WordPosition>(
+    words: &[String],
+    min_overlap: usize,
+    position: F,
+) -> Vec<(usize, WordProblem)> {
+    let mut problems = Vec::new();
+
+    let mut first_of_word: HashMap<&String, usize> = HashMap::with_capacity(words.len());
+    let mut first_of_lowercase: HashMap<String, usize> = HashMap::with_capacity(words.len());
+
+    for (index, word) in words.iter().enumerate() {
+        let lowercase = word.to_lowercase();
+
+        let mut kinds = Vec::new();
+
+        if word.is_empty() {
+            kinds.push(WordProblemKind::Empty);
+        } else if let Some(&first) = first_of_word.get(word) {
+            kinds.push(WordProblemKind::Duplicate {
+                first: position(first),
+            });
+        } else {
+            if let Some(&first) = first_of_lowercase.get(&lowercase) {
+                kinds.push(WordProblemKind::CaseDuplicate {
+                    other: words[first].clone(),
+                    first: position(first),
+                });
+            }
+
+            if word.len() <= min_overlap {
+                kinds.push(WordProblemKind::TooShort);
+            }
+        }
+
+        first_of_word.entry(word).or_insert(index);
+        first_of_lowercase.entry(lowercase).or_insert(index);
+
+        problems.extend(kinds.into_iter().map(|kind| {
+            (
+                index,
+                WordProblem {
+                    word: word.clone(),
+                    position: position(index),
+                    kind,
+                },
+            )
+        }));
+    }
+
+    problems
+}
+
+impl WordList {
+    /// Finds words with problems, including the duplicates that were skipped while reading, and
+    /// deals with them according to `policy`. Returns all problems that were found. With
+    /// `InputPolicy::Error`, they are returned as an error instead, and the words are kept as
+    /// they are.
+    pub fn sanitize(
+        &mut self,
+        min_overlap: usize,
+        policy: InputPolicy,
+    ) -> Result<Vec<WordProblem>, Vec<WordProblem>> {
+        let locations = &self.locations;
+        let words = &self.words;
+
+        let word_problems = find_word_problems(words, min_overlap, |index| {
+            match locations.get(&words[index]) {
+                Some(location) => WordPosition::Location(location.clone()),
+                None => WordPosition::Index(index),
+            }
+        });
+
+        let mut problems = self
+            .duplicates
+            .iter()
+            .map(|duplicate| WordProblem {
+                word: duplicate.word.clone(),
+                position: WordPosition::Location(duplicate.duplicate.clone()),
+                kind: WordProblemKind::Duplicate {
+                    first: WordPosition::Location(duplicate.first.clone()),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if policy == InputPolicy::Error {
+            problems.extend(word_problems.into_iter().map(|(_, problem)| problem));
+
+            return if problems.is_empty() {
+                Ok(problems)
+            } else {
+                Err(problems)
+            };
+        }
+
+        let mut removed = vec![false; self.words.len()];
+
+        for &(index, ref problem) in &word_problems {
+            removed[index] |= match problem.kind {
+                WordProblemKind::Empty | WordProblemKind::Duplicate { .. } => true,
+                WordProblemKind::CaseDuplicate { .. } => policy != InputPolicy::Warn,
+                WordProblemKind::TooShort => policy == InputPolicy::Drop,
+            };
+        }
+
+        problems.extend(word_problems.into_iter().map(|(_, problem)| problem));
+
+        let words = std::mem::take(&mut self.words);
+        let metadata = std::mem::take(&mut self.metadata);
+
+        for ((word, metadata), removed) in words.into_iter().zip(metadata).zip(removed) {
+            if removed {
+                self.locations.remove(&word);
+            } else {
+                self.words.push(word);
+                self.metadata.push(metadata);
+            }
+        }
+
+        Ok(problems)
+    }
+}
+
+impl WordProblemKind {
+    pub fn message(&self) -> &'static str {
+        match self {
+            WordProblemKind::Empty => EMPTY_WORD,
+            WordProblemKind::TooShort => TOO_SHORT_WORD,
+            WordProblemKind::Duplicate { .. } => DUPLICATE_WORD,
+            WordProblemKind::CaseDuplicate { .. } => CASE_DUPLICATE_WORD,
+        }
+    }
+}
+
+impl InputProblem {
+    /// Fatal problems make a search impossible. The others are most likely mistakes, but a
+    /// search can still deal with them.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            InputProblem::Invalid(_) => true,
+            InputProblem::Word(problem) => matches!(
+                problem.kind,
+                WordProblemKind::Empty | WordProblemKind::Duplicate { .. }
+            ),
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            InputProblem::Invalid(message) => message,
+            InputProblem::Word(problem) => problem.kind.message(),
+        }
+    }
+}
+
+impl fmt::Display for WordPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WordPosition::Index(index) => write!(f, "word {}", index + 1),
+            WordPosition::Location(location) => write!(f, "{}", location),
+        }
+    }
+}
+
+impl fmt::Display for WordProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            WordProblemKind::Empty => write!(f, "{}: The word is empty", self.position),
+            WordProblemKind::TooShort => write!(
+                f,
+                "{}: {} is too short to be chained with any other word",
+                self.position, self.word
+            ),
+            WordProblemKind::Duplicate { first } => write!(
+                f,
+                "{}: {} is a duplicate of the word at {}",
+                self.position, self.word, first
+            ),
+            WordProblemKind::CaseDuplicate { other, first } => write!(
+                f,
+                "{}: {} only differs in case from {} at {}",
+                self.position, self.word, other, first
+            ),
+        }
+    }
+}
+
+impl fmt::Display for InputProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputProblem::Invalid(message) => write!(f, "{}", message),
+            InputProblem::Word(problem) => write!(f, "{}", problem),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WordMetadata;
+
+    fn word_list(text: &str) -> WordList {
+        let mut word_list = WordList::new();
+        word_list
+            .read("test", text.as_bytes())
+            .unwrap_or_else(|error| panic!("{}", error));
+        word_list
+    }
+
+    fn describe(problems: &[WordProblem]) -> Vec<String> {
+        problems.iter().map(|problem| problem.to_string()).collect()
+    }
+
+    const WORDS: &str = "Lalo\nlalo | weight=2\nab | weight=3\nOslo | weight=4\nLalo\n";
+
+    #[test]
+    fn problems_are_found_per_word() {
+        let words = ["Lalo", "", "lalo", "Lalo", "a", "LALO", "Oslo"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>();
+
+        let problems = find_word_problems(&words, 1, WordPosition::Index)
+            .into_iter()
+            .map(|(index, problem)| format!("{} {}", index, problem))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            problems,
+            [
+                "1 word 2: The word is empty",
+                "2 word 3: lalo only differs in case from Lalo at word 1",
+                "3 word 4: Lalo is a duplicate of the word at word 1",
+                "4 word 5: a is too short to be chained with any other word",
+                "5 word 6: LALO only differs in case from Lalo at word 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn error_keeps_the_words() {
+        let mut words = word_list(WORDS);
+
+        let problems = match words.sanitize(2, InputPolicy::Error) {
+            Ok(_) => panic!("expected the problems as an error"),
+            Err(problems) => problems,
+        };
+
+        assert_eq!(
+            describe(&problems),
+            [
+                "test:5: Lalo is a duplicate of the word at test:1",
+                "test:2: lalo only differs in case from Lalo at test:1",
+                "test:3: ab is too short to be chained with any other word",
+            ]
+        );
+        assert_eq!(words.words, ["Lalo", "lalo", "ab", "Oslo"]);
+
+        let mut clean = word_list("Lalo\nOslo\n");
+        assert!(
+            matches!(clean.sanitize(2, InputPolicy::Error), Ok(ref problems) if problems.is_empty())
+        );
+    }
+
+    #[test]
+    fn policies_remove_more_and_more_words() {
+        let expected: [(InputPolicy, &[&str], &[u32]); 3] = [
+            (
+                InputPolicy::Warn,
+                &["Lalo", "lalo", "ab", "Oslo"],
+                &[1, 2, 3, 4],
+            ),
+            (InputPolicy::Dedupe, &["Lalo", "ab", "Oslo"], &[1, 3, 4]),
+            (InputPolicy::Drop, &["Lalo", "Oslo"], &[1, 4]),
+        ];
+
+        for &(policy, kept, weights) in &expected {
+            let mut words = word_list(WORDS);
+
+            let problems = match words.sanitize(2, policy) {
+                Ok(problems) => problems,
+                Err(_) => panic!("{:?} shouldn't fail", policy),
+            };
+
+            assert_eq!(problems.len(), 3, "{:?}", policy);
+            assert_eq!(words.words, kept, "{:?}", policy);
+            assert_eq!(
+                words
+                    .metadata
+                    .iter()
+                    .map(|metadata| metadata.weight)
+                    .collect::<Vec<_>>(),
+                weights,
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn empty_words_are_always_removed() {
+        let mut words = word_list("Lalo\nOslo\n");
+        words.words.insert(1, String::new());
+        words.metadata.insert(1, WordMetadata::default());
+
+        let problems = match words.sanitize(1, InputPolicy::Warn) {
+            Ok(problems) => problems,
+            Err(_) => panic!("Warn shouldn't fail"),
+        };
+
+        assert_eq!(describe(&problems), ["word 2: The word is empty"]);
+        assert_eq!(words.words, ["Lalo", "Oslo"]);
+    }
+
+    #[test]
+    fn only_unsearchable_problems_are_fatal() {
+        let mut words = word_list(WORDS);
+        let problems = words
+            .sanitize(2, InputPolicy::Warn)
+            .unwrap_or_else(|_| panic!("Warn shouldn't fail"));
+
+        let fatal = problems
+            .into_iter()
+            .map(|problem| InputProblem::Word(problem).is_fatal())
+            .collect::<Vec<_>>();
+
+        assert_eq!(fatal, [true, false, false]);
+        assert!(InputProblem::Invalid(EMPTY_WORD).is_fatal());
+    }
+}
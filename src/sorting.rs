@@ -17,8 +17,10 @@ pub enum SortingOrder {
     ForFasterIntermediateResults
 }
 
+type SortingFunc = Box<dyn Fn(&WordRating, &WordRating) -> cmp::Ordering>;
+
 impl SortingOrder {
-    fn as_sorting_func(&self) -> Box<dyn Fn(&WordRating, &WordRating) -> cmp::Ordering> {
+    fn as_sorting_func(&self) -> SortingFunc {
 
         match self {
             SortingOrder::ForFasterCompletion => Box::new(|a, b|
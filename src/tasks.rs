@@ -1,10 +1,9 @@
-use super::connectivity::ConnectivityIndexTable;
 use rayon::prelude::*;
 use rayon::iter::repeat;
 
 pub fn create_chain_tasks(
     start_index: u8,
-    connectivity_index_table: &ConnectivityIndexTable,
+    connectivity_index_table: &[Vec<u8>],
     granularity: u8) -> Vec<Vec<u8>> {
 
     let mut tasks = vec![vec![start_index]];
@@ -21,6 +20,12 @@ pub fn create_chain_tasks(
                     .map(ToOwned::to_owned)
                     .collect::<Vec<_>>();
 
+                // Keep chains that end early, since they may be the only ones that satisfy the
+                // constraints of a search
+                if followers.is_empty() {
+                    return vec![t.clone()];
+                }
+
                 repeat(t)
                     .zip(followers)
                     .map(|(old, &next)| {
@@ -31,11 +36,11 @@ pub fn create_chain_tasks(
 
             }).collect::<Vec<Vec<u8>>>();
 
-        if next_gen.len() > 0 {
-            tasks = next_gen;
-        } else {
+        if next_gen == tasks {
             break;
         }
+
+        tasks = next_gen;
     };
 
     tasks
@@ -2,15 +2,17 @@ use std::cmp;
 
 pub fn overlapping_chars(left: &str, right: &str) -> usize {
 
-    debug_assert!(left.len() > 0 && right.len() > 0);
-
     let left = left.to_lowercase();
     let right = right.to_lowercase();
 
-    let mut left = left.chars().as_str();
-    let mut right = right.chars().as_str();
+    // Compare bytes, so trimming can't split a character. Equal bytes always start and end on
+    // character boundaries, since one side starts at the beginning of a word and the other ends at
+    // the end of one.
+    let mut left = left.as_bytes();
+    let mut right = right.as_bytes();
 
-    let max_overlap = cmp::min(left.len(), right.len()) - 1;
+    // Empty and single-character words can't overlap with anything
+    let max_overlap = cmp::min(left.len(), right.len()).saturating_sub(1);
 
     // trim words to maximum potential overlap
     left = &left[left.len()-max_overlap..];
@@ -29,18 +31,19 @@ pub fn overlapping_chars(left: &str, right: &str) -> usize {
     0
 }
 
-pub fn pretty_format_index_chain(sorted_words: &Vec<String>, chain: &Vec<u8>) -> String {
-
-    // TODO: Parser should make sure we have at least one word
-    debug_assert!(chain.len() > 0);
+pub fn pretty_format_index_chain(sorted_words: &[String], chain: &[u8]) -> String {
 
     let mut result = String::new();
 
+    if chain.is_empty() {
+        return result;
+    }
+
     for (left, right) in chain
         .windows(2)
         .map(|win| (&sorted_words[win[0] as usize], &sorted_words[win[1] as usize])) {
 
-        let overlap = overlapping_chars(&left, &right);
+        let overlap = overlapping_chars(left, right);
 
         result.push_str(&left[..left.len() - overlap]);
     };